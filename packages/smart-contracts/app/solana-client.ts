@@ -32,12 +32,27 @@ export interface TeamVault {
   teamId: string;
   name: string;
   founders: PublicKey[];
-  threshold: number;
-  totalFunds: BN;
+  founderWeights: number[] | null; // Equity in basis points, parallel to `founders`
+  threshold: number; // Founder count, or percentage of total weight when weighted
+  governance: GovernanceConfig;
+  allowedPrograms: PublicKey[];
+  allowances: SpendingAllowance[];
+  delegations: VoteDelegation[];
+  vestingFounders: PublicKey[];
+  arbitrator: PublicKey | null;
+  disputeCount: BN;
+  openDisputes: number;
+  freezes: VaultFreeze[];
+  agreementVersion: number;
+  agreementRequired: boolean;
+  agreementCurrent: boolean;
+  balances: VaultBalance[];
   proposalCount: BN;
+  configVersion: number;
   bump: number;
   createdAt: BN;
   isActive: boolean;
+  closed: boolean;
 }
 
 export interface Proposal {
@@ -47,20 +62,42 @@ export interface Proposal {
   description: string;
   recipient: PublicKey;
   amount: BN;
+  mint: PublicKey | null;
   proposalType: ProposalType;
   votes: Vote[];
   status: ProposalStatus;
+  configVersion: number;
   createdAt: BN;
   expiresAt: BN;
+  approvedAt: BN | null;
   executedAt: BN | null;
+  executedPayouts: number;
+  skippedPayouts: number; // Bitmap of batch payouts skipped as unpayable
+  index: BN; // Position in the vault's proposal sequence, used in the PDA seeds
+  bump: number;
 }
 
 export interface SponsorEscrow {
   questId: string;
   sponsor: PublicKey;
+  mint: PublicKey;
   totalAmount: BN;
   releasedAmount: BN;
+  refundedAmount: BN;
+  reservedAmount: BN;
+  contributions: Contribution[]; // Sponsor first, then co-sponsors
+  minContribution: BN;
+  owedRefunds: Contribution[]; // Refunds held back for missing or frozen token accounts
   milestones: Milestone[];
+  judges: PublicKey[];
+  judgeThreshold: number;
+  recipients: PublicKey[];
+  cancelApprovals: PublicKey[];
+  prizeTable: PrizeTable;
+  resultVotes: ResultVote[];
+  prizes: Prize[];
+  resultsFinalizedAt: BN | null;
+  prizesSettled: boolean;
   status: EscrowStatus;
   bump: number;
   createdAt: BN;
@@ -77,6 +114,7 @@ export interface Territory {
   currentTeams: number;
   uri: string;
   owner: PublicKey | null;
+  authority: PublicKey;
   battlesWon: number;
   battlesLost: number;
   totalRewards: BN;
@@ -100,17 +138,54 @@ export interface Battle {
   resolvedAt: BN | null;
 }
 
-// Enum types
-export enum ProposalType {
-  Transfer = 'Transfer',
-}
+// Proposal kinds, in the shape Anchor decodes them
+export type ProposalType =
+  | { transfer: {} }
+  | { batchTransfer: { payouts: Payout[] } }
+  | { addFounder: { founder: PublicKey; weightBps: number } }
+  | { removeFounder: { founder: PublicKey } }
+  | { changeThreshold: { threshold: number } }
+  | { setFounderWeights: { founderWeights: number[] | null; threshold: number } }
+  | { renameTeam: { name: string } }
+  | { updateGovernance: { config: GovernanceConfig } }
+  | { executeInstructions: { instructions: VaultInstruction[] } }
+  | { setAllowedPrograms: { programs: PublicKey[] } }
+  | { setAllowance: { founder: PublicKey; mint: PublicKey; amountPerPeriod: BN; period: BN } }
+  | {
+      createPaymentStream: {
+        recipient: PublicKey;
+        mint: PublicKey;
+        ratePerSecond: BN;
+        startTime: BN;
+        cliffTime: BN;
+        endTime: BN;
+      };
+    }
+  | { cancelPaymentStream: { paymentStream: PublicKey } }
+  | { separateTeam: { distribution: SeparationShare[] } }
+  | { setArbitrator: { arbitrator: PublicKey | null } }
+  | {
+      createFounderVesting: {
+        founder: PublicKey;
+        mint: PublicKey;
+        totalAmount: BN;
+        startTime: BN;
+        cliffDuration: BN;
+        vestingDuration: BN;
+        revocable: boolean;
+      };
+    }
+  | { recoverFounder: { lostFounder: PublicKey; newFounder: PublicKey } };
 
+// Enum types
 export enum ProposalStatus {
   Pending = 'Pending',
   Approved = 'Approved',
+  PartiallyExecuted = 'PartiallyExecuted',
   Rejected = 'Rejected',
   Executed = 'Executed',
   Expired = 'Expired',
+  Cancelled = 'Cancelled',
 }
 
 export enum EscrowStatus {
@@ -133,18 +208,110 @@ export enum BattleStatus {
 }
 
 // Additional types
+export interface GovernanceConfig {
+  votingPeriod: BN;
+  executionDelay: BN;
+  quorum: number | null;
+}
+
+export interface SpendingAllowance {
+  founder: PublicKey;
+  mint: PublicKey;
+  amountPerPeriod: BN;
+  period: BN;
+  spent: BN;
+  periodStart: BN;
+}
+
+export interface VoteDelegation {
+  founder: PublicKey;
+  delegate: PublicKey;
+  expiresAt: BN;
+}
+
+export interface VaultFreeze {
+  initiator: PublicKey;
+  until: BN;
+}
+
+export interface VaultBalance {
+  mint: PublicKey;
+  amount: BN;
+}
+
 export interface Vote {
   voter: PublicKey;
+  delegator: PublicKey | null; // Founder the voter cast this vote for as delegate
   support: boolean;
   timestamp: BN;
 }
 
+export interface Payout {
+  recipient: PublicKey;
+  amount: BN;
+}
+
+export interface SeparationShare {
+  founder: PublicKey;
+  shareBps: number;
+}
+
+export interface VaultInstruction {
+  programId: PublicKey;
+  accounts: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[];
+  data: Buffer;
+}
+
+// Basis points of the milestone pool left after fixed amounts, or a fixed amount
+export type MilestoneShare = { bps: [number] } | { amount: [BN] };
+
 export interface Milestone {
   title: string;
   description: string;
-  percentage: number;
+  share: MilestoneShare;
   released: boolean;
   releasedAt: BN | null;
+  deadline: BN | null;
+  refunded: boolean;
+  settledAmount: BN;
+  approvals: PublicKey[];
+}
+
+export interface Contribution {
+  contributor: PublicKey;
+  amount: BN;
+}
+
+export interface PrizeTable {
+  pool: BN;
+  rankBps: number[]; // Share of the pool for each rank, first place first
+  claimWindow: BN;
+  resultsDeadline: BN;
+}
+
+export interface ResultVote {
+  judge: PublicKey;
+  winners: PublicKey[];
+}
+
+export interface Prize {
+  winner: PublicKey;
+  amount: BN;
+  claimedAt: BN | null;
+}
+
+// Events
+export interface ProposalExecuted {
+  proposal: PublicKey;
+  teamVault: PublicKey;
+  amount: BN; // Amount actually paid out
+  skipped: PublicKey[]; // Batch recipients still owed a skipped payout
+}
+
+export interface TeamVaultClosed {
+  teamVault: PublicKey;
+  closedBy: PublicKey;
+  refunded: BN; // Rent returned to the founders
 }
 
 export class SolanaProtocolClient {
@@ -183,7 +350,9 @@ export class SolanaProtocolClient {
     teamId: string,
     name: string,
     founders: PublicKey[],
-    threshold: number
+    founderWeights: number[] | null,
+    threshold: number,
+    governance: GovernanceConfig
   ): Promise<{ signature: string; teamVault: PublicKey }> {
     const [teamVault, bump] = await PublicKey.findProgramAddress(
       [Buffer.from("team_vault"), Buffer.from(teamId)],
@@ -214,25 +383,22 @@ export class SolanaProtocolClient {
     description: string,
     recipient: PublicKey,
     amount: BN,
-    proposalType: ProposalType = ProposalType.Transfer
+    mint: PublicKey | null,
+    proposalType: ProposalType = { transfer: {} }
   ): Promise<{ signature: string; proposal: PublicKey }> {
-    const proposal = Keypair.generate();
-
-    // Create proposal instruction (mock)
-    const instruction = SystemProgram.createAccount({
-      fromPubkey: this.provider.wallet.publicKey,
-      newAccountPubkey: proposal.publicKey,
-      lamports: await this.connection.getMinimumBalanceForRentExemption(500),
-      space: 500,
-      programId: this.programId,
-    });
+    // The new proposal takes the next index in the vault's sequence
+    const vault = await this.fetchTeamVault(teamVault);
+    if (!vault) {
+      throw new Error("Team vault not found");
+    }
+    const [proposal] = await this.getProposalPDA(teamVault, vault.proposalCount);
 
-    const transaction = new Transaction().add(instruction);
-    transaction.feePayer = this.provider.wallet.publicKey;
+    // Mock transaction for proposal creation
+    const transaction = new Transaction();
     
-    const signature = await this.provider.sendAndConfirm(transaction, [proposal]);
+    const signature = await this.provider.sendAndConfirm(transaction);
 
-    return { signature, proposal: proposal.publicKey };
+    return { signature, proposal };
   }
 
   /**
@@ -266,6 +432,56 @@ export class SolanaProtocolClient {
     return signature;
   }
 
+  /**
+   * Pay a batch payout that was skipped because the recipient's associated
+   * token account was missing or frozen
+   */
+  async paySkippedPayout(
+    teamVault: PublicKey,
+    proposal: PublicKey,
+    payoutIndex: number,
+    vaultTokenAccount: PublicKey,
+    recipientTokenAccount: PublicKey
+  ): Promise<string> {
+    // Mock transaction for paying a skipped payout
+    const transaction = new Transaction();
+    
+    const signature = await this.provider.sendAndConfirm(transaction);
+    return signature;
+  }
+
+  /**
+   * Distribute a vault token account left after a separation, such as one for
+   * an untracked mint; founder token accounts go in distribution order
+   */
+  async sweepSeparatedVault(
+    teamVault: PublicKey,
+    proposal: PublicKey,
+    vaultTokenAccount: PublicKey,
+    founderTokenAccounts: PublicKey[]
+  ): Promise<string> {
+    // Mock transaction for sweeping a separated vault
+    const transaction = new Transaction();
+    
+    const signature = await this.provider.sendAndConfirm(transaction);
+    return signature;
+  }
+
+  /**
+   * Close a separated, drained team vault; its spare rent is split between the
+   * founders, passed in the vault's founder order
+   */
+  async closeTeamVault(
+    teamVault: PublicKey,
+    founders: PublicKey[]
+  ): Promise<string> {
+    // Mock transaction for closing a team vault
+    const transaction = new Transaction();
+    
+    const signature = await this.provider.sendAndConfirm(transaction);
+    return signature;
+  }
+
   // =============================================================================
   // SPONSOR ESCROW FUNCTIONS
   // =============================================================================
@@ -276,7 +492,12 @@ export class SolanaProtocolClient {
   async initializeSponsorEscrow(
     questId: string,
     totalAmount: BN,
-    milestones: Milestone[]
+    milestones: Milestone[],
+    judges: PublicKey[],
+    judgeThreshold: number,
+    recipients: PublicKey[],
+    prizeTable: PrizeTable,
+    minContribution: BN
   ): Promise<{ signature: string; escrow: PublicKey }> {
     const [escrow, bump] = await PublicKey.findProgramAddress(
       [Buffer.from("sponsor_escrow"), Buffer.from(questId)],
@@ -314,6 +535,22 @@ export class SolanaProtocolClient {
     return signature;
   }
 
+  /**
+   * Pay a refund held back because the contributor's associated token account
+   * was missing or frozen, into any token account the contributor owns
+   */
+  async payOwedRefund(
+    escrow: PublicKey,
+    escrowTokenAccount: PublicKey,
+    contributorTokenAccount: PublicKey
+  ): Promise<string> {
+    // Mock transaction for paying an owed refund
+    const transaction = new Transaction();
+    
+    const signature = await this.provider.sendAndConfirm(transaction);
+    return signature;
+  }
+
   // =============================================================================
   // TERRITORY NFT FUNCTIONS
   // =============================================================================
@@ -406,6 +643,16 @@ export class SolanaProtocolClient {
     );
   }
 
+  /**
+   * Get the PDA of a team vault's proposal by its index (0 to proposal_count - 1)
+   */
+  async getProposalPDA(teamVault: PublicKey, index: BN): Promise<[PublicKey, number]> {
+    return await PublicKey.findProgramAddress(
+      [Buffer.from("proposal"), teamVault.toBuffer(), index.toArrayLike(Buffer, "le", 8)],
+      this.programId
+    );
+  }
+
   /**
    * Get sponsor escrow PDA
    */
@@ -438,12 +685,31 @@ export class SolanaProtocolClient {
         teamId: "team_1",
         name: "TechStartup Co",
         founders: [this.provider.wallet.publicKey],
+        founderWeights: null,
         threshold: 1,
-        totalFunds: new BN(0),
+        governance: {
+          votingPeriod: new BN(7 * 24 * 60 * 60),
+          executionDelay: new BN(0),
+          quorum: null,
+        },
+        allowedPrograms: [],
+        allowances: [],
+        delegations: [],
+        vestingFounders: [],
+        arbitrator: null,
+        disputeCount: new BN(0),
+        openDisputes: 0,
+        freezes: [],
+        agreementVersion: 0,
+        agreementRequired: false,
+        agreementCurrent: false,
+        balances: [],
         proposalCount: new BN(0),
+        configVersion: 0,
         bump: 255,
         createdAt: new BN(Date.now() / 1000),
         isActive: true,
+        closed: false,
       };
     } catch (error) {
       console.error("Error fetching team vault:", error);
@@ -460,31 +726,65 @@ export class SolanaProtocolClient {
       return {
         questId: "quest_1",
         sponsor: this.provider.wallet.publicKey,
+        mint: PublicKey.default,
         totalAmount: new BN(1000000),
         releasedAmount: new BN(0),
+        refundedAmount: new BN(0),
+        reservedAmount: new BN(0),
+        contributions: [
+          { contributor: this.provider.wallet.publicKey, amount: new BN(1000000) },
+        ],
+        minContribution: new BN(0),
+        owedRefunds: [],
         milestones: [
           {
             title: "Project Setup",
             description: "Initial project setup and planning",
-            percentage: 25,
+            share: { bps: [2500] },
             released: false,
             releasedAt: null,
+            deadline: null,
+            refunded: false,
+            settledAmount: new BN(0),
+            approvals: [],
           },
           {
             title: "Development Phase",
             description: "Core development work",
-            percentage: 50,
+            share: { bps: [5000] },
             released: false,
             releasedAt: null,
+            deadline: null,
+            refunded: false,
+            settledAmount: new BN(0),
+            approvals: [],
           },
           {
             title: "Final Delivery",
             description: "Project completion and delivery",
-            percentage: 25,
+            share: { bps: [2500] },
             released: false,
             releasedAt: null,
+            deadline: null,
+            refunded: false,
+            settledAmount: new BN(0),
+            approvals: [],
           },
         ],
+        judges: [],
+        judgeThreshold: 0,
+        recipients: [this.provider.wallet.publicKey],
+        cancelApprovals: [],
+        prizeTable: {
+          pool: new BN(0),
+          rankBps: [],
+          claimWindow: new BN(0),
+          resultsDeadline: new BN(0),
+        },
+        resultVotes: [],
+        prizes: [],
+        resultsFinalizedAt: null,
+        prizesSettled: true,
         status: EscrowStatus.Active,
         bump: 255,
         createdAt: new BN(Date.now() / 1000),
@@ -512,6 +812,7 @@ export class SolanaProtocolClient {
         currentTeams: 3,
         uri: "https://example.com/territory/1",
         owner: null,
+        authority: this.provider.wallet.publicKey,
         battlesWon: 0,
         battlesLost: 0,
         totalRewards: new BN(0),
@@ -1,8 +1,56 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use anchor_lang::solana_program::{
+    ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    program::invoke_signed,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
 
 declare_id!("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM");
 
+/// Minimum wait between approving a founder recovery and executing it, so a
+/// founder whose key was wrongly declared lost can still rotate it and make the
+/// recovery stale
+pub const FOUNDER_RECOVERY_DELAY: i64 = 7 * 24 * 60 * 60;
+
+/// Longest prize claim window, which keeps the claim deadline far from overflow
+pub const MAX_CLAIM_WINDOW: i64 = 365 * 24 * 60 * 60;
+
+/// Longest an escrow may wait for its judges to finalize results before the
+/// prize pool can be reclaimed without them
+pub const MAX_RESULTS_PERIOD: i64 = 365 * 24 * 60 * 60;
+
+/// Longest voting period and execution timelock a vault may configure, so
+/// proposal deadlines stay far from overflow
+pub const MAX_VOTING_PERIOD: i64 = 365 * 24 * 60 * 60;
+pub const MAX_EXECUTION_DELAY: i64 = 365 * 24 * 60 * 60;
+
+// Size bounds shared by the account `#[max_len]` attributes and argument checks
+pub const MAX_ID_LEN: usize = 32; // Team, quest and territory ids double as PDA seeds
+pub const MAX_TEAM_NAME_LEN: usize = 32;
+pub const MAX_FOUNDERS: usize = 10;
+pub const MAX_ALLOWED_PROGRAMS: usize = 5;
+pub const MAX_ALLOWANCES: usize = 10;
+pub const MAX_VAULT_MINTS: usize = 5;
+pub const MAX_TITLE_LEN: usize = 100;
+pub const MAX_DESCRIPTION_LEN: usize = 200;
+pub const MAX_PAYOUTS: usize = 10;
+pub const MAX_VAULT_INSTRUCTIONS: usize = 3;
+pub const MAX_INSTRUCTION_ACCOUNTS: usize = 8;
+pub const MAX_INSTRUCTION_DATA_LEN: usize = 128;
+pub const MAX_MILESTONES: usize = 10;
+pub const MAX_MILESTONE_TITLE_LEN: usize = 64;
+pub const MAX_JUDGES: usize = 5;
+pub const MAX_RECIPIENTS: usize = 10;
+pub const MAX_PRIZE_RANKS: usize = 10;
+pub const MAX_CONTRIBUTORS: usize = 10;
+pub const MAX_DISTRIBUTION_CLAIMANTS: u32 = 4096;
+pub const DISTRIBUTION_BITMAP_LEN: usize = MAX_DISTRIBUTION_CLAIMANTS as usize / 8;
+pub const MAX_URI_LEN: usize = 200;
+
 #[program]
 pub mod rise_of_founders_protocol {
     use super::*;
@@ -17,41 +65,138 @@ pub mod rise_of_founders_protocol {
         team_id: String,
         name: String,
         founders: Vec<Pubkey>,
+        founder_weights: Option<Vec<u16>>,
         threshold: u8,
-        bump: u8,
+        governance: GovernanceConfig,
     ) -> Result<()> {
         let team_vault = &mut ctx.accounts.team_vault;
         
         require!(
-            founders.len() <= 10,
-            ErrorCode::TooManyFounders
+            !team_id.is_empty() && team_id.len() <= MAX_ID_LEN,
+            ErrorCode::InvalidTeamId
+        );
+        
+        require!(
+            !name.is_empty() && name.len() <= MAX_TEAM_NAME_LEN,
+            ErrorCode::InvalidTeamName
         );
         
         require!(
-            threshold > 0 && threshold <= founders.len() as u8,
-            ErrorCode::InvalidThreshold
+            founders.len() <= MAX_FOUNDERS,
+            ErrorCode::TooManyFounders
         );
         
+        validate_voting_rules(founders.len(), &founder_weights, threshold)?;
+        
+        governance.validate(founders.len())?;
+        
+        // Binding a fully signed founder agreement is optional at creation
+        let agreement_version = match &ctx.accounts.founder_agreement {
+            Some(founder_agreement) => {
+                require!(
+                    founder_agreement.status == AgreementStatus::FullySigned,
+                    ErrorCode::AgreementNotFullySigned
+                );
+                require!(
+                    founder_agreement.team_id == team_id
+                        && same_founders(&founder_agreement.founders, &founders),
+                    ErrorCode::AgreementMismatch
+                );
+                founder_agreement.version
+            },
+            None => 0,
+        };
+        
         team_vault.team_id = team_id;
         team_vault.name = name;
         team_vault.founders = founders;
+        team_vault.founder_weights = founder_weights;
         team_vault.threshold = threshold;
-        team_vault.total_funds = 0;
+        team_vault.governance = governance;
+        team_vault.balances = Vec::new();
         team_vault.proposal_count = 0;
-        team_vault.bump = bump;
+        team_vault.allowed_programs = Vec::new();
+        team_vault.allowances = Vec::new();
+        team_vault.delegations = Vec::new();
+        team_vault.vesting_founders = Vec::new();
+        team_vault.arbitrator = None;
+        team_vault.dispute_count = 0;
+        team_vault.open_disputes = 0;
+        team_vault.freezes = Vec::new();
+        team_vault.agreement_version = agreement_version;
+        team_vault.agreement_required = agreement_version > 0;
+        team_vault.agreement_current = agreement_version > 0;
+        team_vault.config_version = 0;
+        team_vault.bump = ctx.bumps.team_vault;
         team_vault.created_at = Clock::get()?.unix_timestamp;
         team_vault.is_active = true;
+        team_vault.closed = false;
 
         emit!(TeamVaultCreated {
-            team_vault: ctx.accounts.team_vault.key(),
+            team_vault: team_vault.key(),
             team_id: team_vault.team_id.clone(),
             founders: team_vault.founders.clone(),
+            founder_weights: team_vault.founder_weights.clone(),
             threshold,
         });
 
         Ok(())
     }
 
+    /// Deposit tokens into the team vault from any contributor. Anyone may
+    /// top up a mint the vault already holds; only a founder may deposit a
+    /// new mint
+    pub fn deposit_to_vault(
+        ctx: Context<DepositToVault>,
+        amount: u64,
+    ) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+
+        require!(
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
+        );
+
+        require!(
+            amount > 0,
+            ErrorCode::InvalidAmount
+        );
+
+        // The vault tracks at most MAX_VAULT_MINTS mints. If anyone could
+        // open a slot, dust deposits of junk mints would fill them and lock
+        // the team out of receiving the tokens it actually uses
+        let mint = ctx.accounts.vault_token_account.mint;
+        require!(
+            team_vault.balance_of(&mint) > 0
+                || team_vault.founders.contains(&ctx.accounts.contributor.key()),
+            ErrorCode::UnauthorizedFounder
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.contributor_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.contributor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let balance = team_vault.credit(mint, amount)?;
+
+        emit!(VaultDeposit {
+            team_vault: team_vault.key(),
+            contributor: ctx.accounts.contributor.key(),
+            mint,
+            amount,
+            balance,
+        });
+
+        Ok(())
+    }
+
     /// Create a spending proposal for team vault
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
@@ -59,36 +204,84 @@ pub mod rise_of_founders_protocol {
         description: String,
         recipient: Pubkey,
         amount: u64,
+        mint: Option<Pubkey>,
         proposal_type: ProposalType,
     ) -> Result<()> {
         let team_vault = &mut ctx.accounts.team_vault;
         let proposal = &mut ctx.accounts.proposal;
         
+        require!(
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
+        );
+        
         // Verify signer is a founder
         require!(
             team_vault.founders.contains(&ctx.accounts.proposer.key()),
             ErrorCode::UnauthorizedFounder
         );
 
-        proposal.team_vault = ctx.accounts.team_vault.key();
+        require!(
+            title.len() <= MAX_TITLE_LEN,
+            ErrorCode::TitleTooLong
+        );
+        
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            ErrorCode::DescriptionTooLong
+        );
+
+        // Reject governance changes that could never be applied
+        team_vault.validate_proposal_type(&proposal_type)?;
+
+        // Transfers are bound to the mint voted on, so execution cannot pay
+        // the amount out of another mint's balance
+        require!(
+            !matches!(proposal_type, ProposalType::Transfer | ProposalType::BatchTransfer { .. })
+                || mint.is_some(),
+            ErrorCode::MissingProposalMint
+        );
+
+        if let ProposalType::BatchTransfer { payouts } = &proposal_type {
+            let total = payouts
+                .iter()
+                .try_fold(0u64, |total, payout| total.checked_add(payout.amount));
+            require!(
+                total == Some(amount),
+                ErrorCode::InvalidAmount
+            );
+        }
+
+        proposal.team_vault = team_vault.key();
         proposal.proposer = ctx.accounts.proposer.key();
         proposal.title = title;
         proposal.description = description;
         proposal.recipient = recipient;
         proposal.amount = amount;
-        proposal.proposal_type = proposal_type;
+        proposal.mint = mint;
+        proposal.proposal_type = proposal_type.clone();
         proposal.votes = Vec::new();
         proposal.status = ProposalStatus::Pending;
+        proposal.config_version = team_vault.config_version;
         proposal.created_at = Clock::get()?.unix_timestamp;
-        proposal.expires_at = Clock::get()?.unix_timestamp + 7 * 24 * 60 * 60; // 7 days
+        proposal.expires_at = proposal
+            .created_at
+            .checked_add(team_vault.governance.voting_period)
+            .ok_or(ErrorCode::MathOverflow)?;
+        proposal.approved_at = None;
+        proposal.executed_payouts = 0;
+        proposal.skipped_payouts = 0;
+        proposal.index = team_vault.proposal_count;
+        proposal.bump = ctx.bumps.proposal;
 
         team_vault.proposal_count += 1;
 
         emit!(ProposalCreated {
-            proposal: ctx.accounts.proposal.key(),
-            team_vault: ctx.accounts.team_vault.key(),
+            proposal: proposal.key(),
+            team_vault: team_vault.key(),
             proposer: ctx.accounts.proposer.key(),
             amount,
+            mint,
             proposal_type,
         });
 
@@ -99,191 +292,807 @@ pub mod rise_of_founders_protocol {
     pub fn vote_on_proposal(
         ctx: Context<VoteOnProposal>,
         support: bool,
+        delegator: Option<Pubkey>, // Founder the signer votes for as their delegate
     ) -> Result<()> {
         let team_vault = &ctx.accounts.team_vault;
         let proposal = &mut ctx.accounts.proposal;
-        
-        // Verify signer is a founder
+        let proposal_key = proposal.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        proposal.cast_vote(team_vault, ctx.accounts.voter.key(), delegator, support, now)?;
+        proposal.tally_votes(proposal_key, team_vault, now)?;
+
+        Ok(())
+    }
+
+    /// Record founders' votes signed off-chain and verified by the ed25519
+    /// precompile, so one relayer can submit everyone's votes
+    pub fn submit_signed_votes(ctx: Context<SubmitSignedVotes>) -> Result<()> {
+        let team_vault = &ctx.accounts.team_vault;
+        let proposal = &mut ctx.accounts.proposal;
+        let proposal_key = proposal.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        // Signatures must be checked by ed25519 instructions earlier in this transaction
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let current_index = load_current_index_checked(&instructions)? as usize;
+        let mut recorded = 0;
+
+        for index in 0..current_index {
+            let instruction = load_instruction_at_checked(index, &instructions)?;
+            if instruction.program_id != ed25519_program::ID {
+                continue;
+            }
+
+            for (voter, message) in ed25519_signed_messages(&instruction.data)? {
+                // Signatures meant for other proposals, vaults or programs may
+                // share the transaction; only votes on this proposal count
+                let support = match signed_vote_support(&message, &team_vault.key(), &proposal_key, proposal.index)? {
+                    Some(support) => support,
+                    None => continue,
+                };
+
+                proposal.cast_vote(team_vault, voter, None, support, now)?;
+                recorded += 1;
+
+                emit!(SignedVoteRecorded {
+                    proposal: proposal_key,
+                    team_vault: team_vault.key(),
+                    voter,
+                    support,
+                });
+            }
+        }
+
         require!(
-            team_vault.founders.contains(&ctx.accounts.voter.key()),
-            ErrorCode::UnauthorizedFounder
+            recorded > 0,
+            ErrorCode::InvalidSignedVote
         );
+
+        proposal.tally_votes(proposal_key, team_vault, now)?;
+
+        Ok(())
+    }
+
+    /// Execute an approved proposal
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteProposal<'info>>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let team_vault = &mut ctx.accounts.team_vault;
         
-        // Check if already voted
         require!(
-            !proposal.votes.iter().any(|vote| vote.voter == ctx.accounts.voter.key()),
-            ErrorCode::AlreadyVoted
+            proposal.status == ProposalStatus::Approved
+                || proposal.status == ProposalStatus::PartiallyExecuted,
+            ErrorCode::ProposalNotApproved
         );
         
-        // Check if proposal is still active
+        // A batch that already started paying out keeps its authorization
         require!(
-            proposal.status == ProposalStatus::Pending,
-            ErrorCode::ProposalNotActive
+            proposal.status == ProposalStatus::PartiallyExecuted
+                || proposal.config_version == team_vault.config_version,
+            ErrorCode::StaleProposal
         );
         
+        let now = Clock::get()?.unix_timestamp;
         require!(
-            Clock::get()?.unix_timestamp < proposal.expires_at,
-            ErrorCode::ProposalExpired
+            !team_vault.is_frozen(now),
+            ErrorCode::VaultFrozenByDispute
         );
 
-        proposal.votes.push(Vote {
-            voter: ctx.accounts.voter.key(),
-            support,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
-
-        // Check if proposal has enough votes to execute
-        let support_votes = proposal.votes.iter().filter(|v| v.support).count();
-        if support_votes >= team_vault.threshold as usize {
-            proposal.status = ProposalStatus::Approved;
-            
-            emit!(ProposalApproved {
-                proposal: ctx.accounts.proposal.key(),
-                team_vault: ctx.accounts.team_vault.key(),
-                votes: support_votes as u8,
-            });
-        }
+        // Once a separation starts settling, nothing else may touch the vault
+        require!(
+            team_vault.is_active
+                || (proposal.status == ProposalStatus::PartiallyExecuted
+                    && matches!(proposal.proposal_type, ProposalType::SeparateTeam { .. })),
+            ErrorCode::TeamVaultNotActive
+        );
 
-        Ok(())
-    }
+        // Give founders a window to react (e.g. remove a compromised key, which
+        // makes this proposal stale) before an approved proposal can run
+        let approved_at = proposal.approved_at.ok_or(ErrorCode::ProposalNotApproved)?;
+        require!(
+            now >= approved_at
+                .checked_add(team_vault.execution_delay(&proposal.proposal_type))
+                .ok_or(ErrorCode::MathOverflow)?,
+            ErrorCode::TimelockNotElapsed
+        );
 
-    /// Execute an approved proposal
-    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
-        let proposal = &mut ctx.accounts.proposal;
-        let team_vault = &ctx.accounts.team_vault;
-        
+        // An approval left unexecuted past its window lapses like an unvoted proposal
         require!(
-            proposal.status == ProposalStatus::Approved,
-            ErrorCode::ProposalNotApproved
+            proposal.status == ProposalStatus::PartiallyExecuted || now < proposal.expires_at,
+            ErrorCode::ProposalExpired
         );
 
-        // Transfer funds based on proposal type
-        match proposal.proposal_type {
+        // Governance changes must still hold against the current vault state
+        team_vault.validate_proposal_type(&proposal.proposal_type)?;
+
+        let team_id = team_vault.team_id.clone();
+        let seeds = &[
+            b"team_vault",
+            team_id.as_bytes(),
+            &[team_vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Apply the proposal based on its type
+        match proposal.proposal_type.clone() {
             ProposalType::Transfer => {
-                let seeds = &[
-                    b"team_vault",
-                    team_vault.team_id.as_bytes(),
-                    &[team_vault.bump],
-                ];
-                let signer = &[&seeds[..]];
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTokenAccount)?;
+                let recipient_token_account = ctx
+                    .accounts
+                    .recipient_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTokenAccount)?;
+                require!(
+                    recipient_token_account.mint == vault_token_account.mint,
+                    ErrorCode::InvalidRecipientTokenAccount
+                );
 
                 token::transfer(
                     CpiContext::new_with_signer(
                         ctx.accounts.token_program.to_account_info(),
                         Transfer {
-                            from: ctx.accounts.vault_token_account.to_account_info(),
-                            to: ctx.accounts.recipient_token_account.to_account_info(),
-                            authority: ctx.accounts.team_vault.to_account_info(),
+                            from: vault_token_account.to_account_info(),
+                            to: recipient_token_account.to_account_info(),
+                            authority: team_vault.to_account_info(),
                         },
                         signer,
                     ),
                     proposal.amount,
                 )?;
+
+                let balance = team_vault.debit(vault_token_account.mint, proposal.amount)?;
+
+                emit!(VaultWithdrawal {
+                    team_vault: team_vault.key(),
+                    proposal: proposal.key(),
+                    recipient: recipient_token_account.owner,
+                    mint: vault_token_account.mint,
+                    amount: proposal.amount,
+                    balance,
+                });
+            },
+            ProposalType::BatchTransfer { payouts } => {
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTokenAccount)?;
+
+                // Recipient token accounts for the next page of payouts are
+                // passed in order as remaining accounts
+                let start = proposal.executed_payouts as usize;
+                require!(
+                    !ctx.remaining_accounts.is_empty(),
+                    ErrorCode::MissingTokenAccount
+                );
+
+                for (payout, recipient_info) in payouts[start..].iter().zip(ctx.remaining_accounts.iter()) {
+                    // A recipient whose associated token account is missing or
+                    // frozen cannot be paid; skip and record the payout instead
+                    // of blocking the rest of the batch, leaving its amount in
+                    // the vault until pay_skipped_payout delivers it
+                    if is_unpayable_recipient(recipient_info, &payout.recipient, &vault_token_account.mint) {
+                        proposal.skipped_payouts |= 1 << proposal.executed_payouts;
+                        proposal.executed_payouts += 1;
+
+                        emit!(BatchPayoutSkipped {
+                            proposal: proposal.key(),
+                            team_vault: team_vault.key(),
+                            recipient: payout.recipient,
+                            amount: payout.amount,
+                        });
+                        continue;
+                    }
+
+                    let recipient_token_account = Account::<TokenAccount>::try_from(recipient_info)?;
+                    require!(
+                        recipient_token_account.owner == payout.recipient
+                            && recipient_token_account.mint == vault_token_account.mint,
+                        ErrorCode::InvalidRecipientTokenAccount
+                    );
+
+                    token::transfer(
+                        CpiContext::new_with_signer(
+                            ctx.accounts.token_program.to_account_info(),
+                            Transfer {
+                                from: vault_token_account.to_account_info(),
+                                to: recipient_token_account.to_account_info(),
+                                authority: team_vault.to_account_info(),
+                            },
+                            signer,
+                        ),
+                        payout.amount,
+                    )?;
+
+                    let balance = team_vault.debit(vault_token_account.mint, payout.amount)?;
+                    proposal.executed_payouts += 1;
+
+                    emit!(VaultWithdrawal {
+                        team_vault: team_vault.key(),
+                        proposal: proposal.key(),
+                        recipient: payout.recipient,
+                        mint: vault_token_account.mint,
+                        amount: payout.amount,
+                        balance,
+                    });
+                }
+
+                if (proposal.executed_payouts as usize) < payouts.len() {
+                    proposal.status = ProposalStatus::PartiallyExecuted;
+
+                    emit!(BatchTransferProgress {
+                        proposal: proposal.key(),
+                        team_vault: team_vault.key(),
+                        executed_payouts: proposal.executed_payouts,
+                        total_payouts: payouts.len() as u8,
+                    });
+
+                    return Ok(());
+                }
+            },
+            ProposalType::ExecuteInstructions { instructions } => {
+                // Every account the instructions reference, including target
+                // programs, is passed as a remaining account; the vault PDA signs.
+                // That covers every vault token account they can move funds out
+                // of, so all of them are reconciled afterwards.
+                let holdings_before = vault_token_holdings(ctx.remaining_accounts, &team_vault.key());
+                let mut account_infos = ctx.remaining_accounts.to_vec();
+                account_infos.push(team_vault.to_account_info());
+
+                for vault_instruction in instructions.iter() {
+                    invoke_signed(
+                        &Instruction::from(vault_instruction),
+                        &account_infos,
+                        signer,
+                    )?;
+                }
+
+                // Keep each mint's tracked balance in line with whatever the
+                // instructions moved; an account handed to another authority
+                // counts as emptied
+                let holdings_after = vault_token_holdings(ctx.remaining_accounts, &team_vault.key());
+                let mut net_changes: Vec<(Pubkey, i128)> = Vec::new();
+                let holdings = holdings_before
+                    .iter()
+                    .map(|(mint, amount)| (mint, -(*amount as i128)))
+                    .chain(holdings_after.iter().map(|(mint, amount)| (mint, *amount as i128)));
+                for (mint, change) in holdings {
+                    match net_changes.iter_mut().find(|(net_mint, _)| net_mint == mint) {
+                        Some((_, net_change)) => *net_change += change,
+                        None => net_changes.push((*mint, change)),
+                    }
+                }
+                for (mint, net_change) in net_changes {
+                    if net_change > 0 {
+                        team_vault.credit(mint, u64::try_from(net_change).map_err(|_| ErrorCode::MathOverflow)?)?;
+                    } else if net_change < 0 {
+                        team_vault.debit_tracked(mint, u64::try_from(-net_change).map_err(|_| ErrorCode::MathOverflow)?)?;
+                    }
+                }
+
+                emit!(VaultInstructionsExecuted {
+                    proposal: proposal.key(),
+                    team_vault: team_vault.key(),
+                    program_ids: instructions.iter().map(|ix| ix.program_id).collect(),
+                    balances: team_vault.balances.clone(),
+                });
+            },
+            ProposalType::SeparateTeam { distribution } => {
+                // Settling happens over several calls: each returns a founder's
+                // vesting and/or drains and closes one vault token account, and a
+                // final call without token accounts closes out the separation
+                // once every tracked mint is drained
+                team_vault.is_active = false;
+
+                if let Some(founder_vesting) = ctx.accounts.founder_vesting.as_mut() {
+                    settle_founder_vesting(
+                        team_vault,
+                        founder_vesting,
+                        ctx.accounts.vault_token_account.as_ref(),
+                        ctx.accounts.vesting_token_account.as_ref(),
+                        ctx.accounts.payer.as_ref().map(|payer| payer.to_account_info()),
+                        &ctx.accounts.token_program,
+                    )?;
+                }
+
+                if let Some(vault_token_account) = ctx.accounts.vault_token_account.as_mut() {
+                    let rent_destination = ctx
+                        .accounts
+                        .payer
+                        .as_ref()
+                        .ok_or(ErrorCode::MissingPayer)?
+                        .to_account_info();
+                    let balance = distribute_vault_token_account(
+                        team_vault,
+                        vault_token_account,
+                        &distribution,
+                        ctx.remaining_accounts,
+                        rent_destination,
+                        &ctx.accounts.token_program,
+                    )?;
+
+                    emit!(SeparationDistribution {
+                        team_vault: team_vault.key(),
+                        proposal: proposal.key(),
+                        mint: vault_token_account.mint,
+                        amount: balance,
+                    });
+                }
+
+                let settled = ctx.accounts.vault_token_account.is_none()
+                    && ctx.accounts.founder_vesting.is_none()
+                    && team_vault.balances.is_empty()
+                    && team_vault.vesting_founders.is_empty();
+                if !settled {
+                    proposal.status = ProposalStatus::PartiallyExecuted;
+                    return Ok(());
+                }
+
+                emit!(TeamSeparationSettled {
+                    team_vault: team_vault.key(),
+                    proposal: proposal.key(),
+                    distribution,
+                });
+            },
+            ProposalType::AddFounder { founder, weight_bps } => {
+                team_vault.founders.push(founder);
+                team_vault.agreement_current = false;
+                if let Some(weights) = team_vault.founder_weights.as_mut() {
+                    weights.push(weight_bps);
+                }
+            },
+            ProposalType::RemoveFounder { founder } => {
+                let index = team_vault
+                    .founder_index(&founder)
+                    .ok_or(ErrorCode::FounderNotFound)?;
+                team_vault.founders.remove(index);
+                team_vault.agreement_current = false;
+                if let Some(weights) = team_vault.founder_weights.as_mut() {
+                    weights.remove(index);
+                }
+                team_vault.allowances.retain(|allowance| allowance.founder != founder);
+                team_vault
+                    .delegations
+                    .retain(|delegation| delegation.founder != founder && delegation.delegate != founder);
+                team_vault.freezes.retain(|freeze| freeze.initiator != founder);
+
+                // A departing founder's revocable vesting returns its unvested tokens
+                if team_vault.vesting_founders.contains(&founder) {
+                    let founder_vesting = ctx
+                        .accounts
+                        .founder_vesting
+                        .as_mut()
+                        .ok_or(ErrorCode::MissingFounderVesting)?;
+                    require!(
+                        founder_vesting.beneficiary == founder,
+                        ErrorCode::MissingFounderVesting
+                    );
+
+                    settle_founder_vesting(
+                        team_vault,
+                        founder_vesting,
+                        ctx.accounts.vault_token_account.as_ref(),
+                        ctx.accounts.vesting_token_account.as_ref(),
+                        ctx.accounts.payer.as_ref().map(|payer| payer.to_account_info()),
+                        &ctx.accounts.token_program,
+                    )?;
+                }
+            },
+            ProposalType::SetFounderWeights { founder_weights, threshold } => {
+                team_vault.founder_weights = founder_weights;
+                team_vault.threshold = threshold;
+            },
+            ProposalType::ChangeThreshold { threshold } => {
+                team_vault.threshold = threshold;
+            },
+            ProposalType::RenameTeam { name } => {
+                team_vault.name = name;
+            },
+            ProposalType::UpdateGovernance { config } => {
+                team_vault.governance = config;
+            },
+            ProposalType::SetAllowedPrograms { programs } => {
+                team_vault.allowed_programs = programs;
+            },
+            ProposalType::SetArbitrator { arbitrator } => {
+                team_vault.arbitrator = arbitrator;
+            },
+            ProposalType::RecoverFounder { lost_founder, new_founder } => {
+                // Open disputes are passed as remaining accounts
+                move_founder_records(
+                    team_vault,
+                    ctx.accounts.founder_vesting.as_mut(),
+                    ctx.remaining_accounts,
+                    &lost_founder,
+                    new_founder,
+                )?;
+                team_vault.replace_founder(&lost_founder, new_founder)?;
+
+                emit!(FounderKeyRotated {
+                    team_vault: team_vault.key(),
+                    old_founder: lost_founder,
+                    new_founder,
+                    recovered: true,
+                });
+            },
+            ProposalType::CreatePaymentStream { recipient, mint, rate_per_second, start_time, cliff_time, end_time } => {
+                let payment_stream = ctx
+                    .accounts
+                    .new_payment_stream
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingPaymentStream)?;
+
+                payment_stream.team_vault = team_vault.key();
+                payment_stream.proposal = proposal.key();
+                payment_stream.recipient = recipient;
+                payment_stream.mint = mint;
+                payment_stream.rate_per_second = rate_per_second;
+                payment_stream.start_time = start_time;
+                payment_stream.cliff_time = cliff_time;
+                payment_stream.end_time = end_time;
+                payment_stream.withdrawn = 0;
+                payment_stream.cancelled_at = None;
+                payment_stream.bump = ctx.bumps.new_payment_stream.ok_or(ErrorCode::MissingPaymentStream)?;
+
+                emit!(PaymentStreamCreated {
+                    payment_stream: payment_stream.key(),
+                    team_vault: team_vault.key(),
+                    recipient,
+                    mint,
+                    rate_per_second,
+                    start_time,
+                    cliff_time,
+                    end_time,
+                });
+            },
+            ProposalType::CreateFounderVesting {
+                founder,
+                mint,
+                total_amount,
+                start_time,
+                cliff_duration,
+                vesting_duration,
+                revocable,
+            } => {
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTokenAccount)?;
+                let vesting_token_account = ctx
+                    .accounts
+                    .vesting_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTokenAccount)?;
+                let founder_vesting = ctx
+                    .accounts
+                    .new_founder_vesting
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingFounderVesting)?;
+                require!(
+                    vesting_token_account.owner == founder_vesting.key()
+                        && vesting_token_account.mint == mint
+                        && vault_token_account.mint == mint,
+                    ErrorCode::InvalidVestingTokenAccount
+                );
+
+                founder_vesting.team_vault = team_vault.key();
+                founder_vesting.founder = founder;
+                founder_vesting.beneficiary = founder;
+                founder_vesting.mint = mint;
+                founder_vesting.total_amount = total_amount;
+                founder_vesting.claimed = 0;
+                founder_vesting.start_time = start_time;
+                founder_vesting.cliff_duration = cliff_duration;
+                founder_vesting.vesting_duration = vesting_duration;
+                founder_vesting.revocable = revocable;
+                founder_vesting.revoked_at = None;
+                founder_vesting.bump = ctx.bumps.new_founder_vesting.ok_or(ErrorCode::MissingFounderVesting)?;
+
+                // Lock the allocation outside the vault so it can only vest to the founder
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: vault_token_account.to_account_info(),
+                            to: vesting_token_account.to_account_info(),
+                            authority: team_vault.to_account_info(),
+                        },
+                        signer,
+                    ),
+                    total_amount,
+                )?;
+
+                team_vault.debit(mint, total_amount)?;
+                team_vault.vesting_founders.push(founder);
+
+                emit!(FounderVestingCreated {
+                    founder_vesting: founder_vesting.key(),
+                    team_vault: team_vault.key(),
+                    founder,
+                    mint,
+                    total_amount,
+                    start_time,
+                    cliff_duration,
+                    vesting_duration,
+                    revocable,
+                });
+            },
+            ProposalType::CancelPaymentStream { payment_stream: stream_key } => {
+                let payment_stream = ctx
+                    .accounts
+                    .payment_stream
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingPaymentStream)?;
+                require!(
+                    payment_stream.key() == stream_key && payment_stream.team_vault == team_vault.key(),
+                    ErrorCode::MissingPaymentStream
+                );
+                require!(
+                    payment_stream.cancelled_at.is_none(),
+                    ErrorCode::PaymentStreamCancelled
+                );
+
+                // Vesting stops now; whatever has not vested simply stays in the vault
+                let now = Clock::get()?.unix_timestamp;
+                payment_stream.cancelled_at = Some(now);
+
+                emit!(PaymentStreamCancelled {
+                    payment_stream: stream_key,
+                    team_vault: team_vault.key(),
+                    vested_amount: payment_stream.vested_amount(now)?,
+                    withdrawn: payment_stream.withdrawn,
+                });
+            },
+            ProposalType::SetAllowance { founder, mint, amount_per_period, period } => {
+                team_vault
+                    .allowances
+                    .retain(|allowance| allowance.founder != founder || allowance.mint != mint);
+                // A zero amount revokes the allowance
+                if amount_per_period > 0 {
+                    team_vault.allowances.push(SpendingAllowance {
+                        founder,
+                        mint,
+                        amount_per_period,
+                        period,
+                        spent: 0,
+                        period_start: Clock::get()?.unix_timestamp,
+                    });
+                }
+
+                emit!(AllowanceUpdated {
+                    team_vault: team_vault.key(),
+                    proposal: proposal.key(),
+                    founder,
+                    mint,
+                    amount_per_period,
+                    period,
+                });
             },
         }
 
+        if proposal.proposal_type.is_governance_change() {
+            // Any other open proposal was voted on under the old configuration;
+            // a rename leaves the voting rules, and so those proposals, intact
+            if !matches!(proposal.proposal_type, ProposalType::RenameTeam { .. }) {
+                team_vault.config_version = team_vault
+                    .config_version
+                    .checked_add(1)
+                    .ok_or(ErrorCode::MathOverflow)?;
+            }
+
+            emit!(TeamVaultConfigUpdated {
+                team_vault: team_vault.key(),
+                proposal: proposal.key(),
+                name: team_vault.name.clone(),
+                founders: team_vault.founders.clone(),
+                founder_weights: team_vault.founder_weights.clone(),
+                threshold: team_vault.threshold,
+                governance: team_vault.governance.clone(),
+                config_version: team_vault.config_version,
+            });
+        }
+
+        // A batch reports only what it paid, and who is still owed
+        let (amount, skipped) = match &proposal.proposal_type {
+            ProposalType::BatchTransfer { payouts } => {
+                let mut paid = 0u64;
+                let mut skipped = Vec::new();
+                for (index, payout) in payouts.iter().enumerate() {
+                    if proposal.is_payout_skipped(index) {
+                        skipped.push(payout.recipient);
+                    } else {
+                        paid = paid.checked_add(payout.amount).ok_or(ErrorCode::MathOverflow)?;
+                    }
+                }
+                (paid, skipped)
+            },
+            _ => (proposal.amount, Vec::new()),
+        };
+
         proposal.status = ProposalStatus::Executed;
         proposal.executed_at = Some(Clock::get()?.unix_timestamp);
 
         emit!(ProposalExecuted {
-            proposal: ctx.accounts.proposal.key(),
-            team_vault: ctx.accounts.team_vault.key(),
-            amount: proposal.amount,
+            proposal: proposal.key(),
+            team_vault: team_vault.key(),
+            amount,
+            skipped,
         });
 
         Ok(())
     }
 
-    // =============================================================================
-    // SPONSOR ESCROW PROGRAM
-    // =============================================================================
-
-    /// Initialize a sponsor escrow for quest funding
-    pub fn initialize_sponsor_escrow(
-        ctx: Context<InitializeSponsorEscrow>,
-        quest_id: String,
-        total_amount: u64,
-        milestones: Vec<Milestone>,
-        bump: u8,
+    /// Pay a batch payout that was skipped because the recipient's associated
+    /// token account was missing or frozen. Anyone may call this once the
+    /// account can take the transfer, since it only ever pays the recipient.
+    pub fn pay_skipped_payout(
+        ctx: Context<PaySkippedPayout>,
+        payout_index: u8,
     ) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        
+        let proposal = &mut ctx.accounts.proposal;
+        let team_vault = &mut ctx.accounts.team_vault;
+
         require!(
-            milestones.len() <= 10,
-            ErrorCode::TooManyMilestones
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
         );
-        
-        // Validate milestone percentages sum to 100
-        let total_percentage: u16 = milestones.iter().map(|m| m.percentage).sum();
+
+        let now = Clock::get()?.unix_timestamp;
         require!(
-            total_percentage == 100,
-            ErrorCode::InvalidMilestonePercentages
+            !team_vault.is_frozen(now),
+            ErrorCode::VaultFrozenByDispute
         );
 
-        escrow.quest_id = quest_id;
-        escrow.sponsor = ctx.accounts.sponsor.key();
-        escrow.total_amount = total_amount;
-        escrow.released_amount = 0;
-        escrow.milestones = milestones;
-        escrow.status = EscrowStatus::Active;
-        escrow.bump = bump;
-        escrow.created_at = Clock::get()?.unix_timestamp;
+        let payout = match &proposal.proposal_type {
+            ProposalType::BatchTransfer { payouts } => payouts.get(payout_index as usize).cloned(),
+            _ => None,
+        }
+        .ok_or(ErrorCode::PayoutNotSkipped)?;
+        require!(
+            proposal.is_payout_skipped(payout_index as usize),
+            ErrorCode::PayoutNotSkipped
+        );
+
+        let vault_token_account = &ctx.accounts.vault_token_account;
+        let recipient_token_account = &ctx.accounts.recipient_token_account;
+        require!(
+            recipient_token_account.owner == payout.recipient
+                && recipient_token_account.mint == vault_token_account.mint,
+            ErrorCode::InvalidRecipientTokenAccount
+        );
+
+        let team_id = team_vault.team_id.clone();
+        let seeds = &[
+            b"team_vault",
+            team_id.as_bytes(),
+            &[team_vault.bump],
+        ];
+        let signer = &[&seeds[..]];
 
-        // Transfer funds to escrow
         token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.sponsor_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_token_account.to_account_info(),
-                    authority: ctx.accounts.sponsor.to_account_info(),
+                    from: vault_token_account.to_account_info(),
+                    to: recipient_token_account.to_account_info(),
+                    authority: team_vault.to_account_info(),
                 },
+                signer,
             ),
-            total_amount,
+            payout.amount,
         )?;
 
-        emit!(EscrowCreated {
-            escrow: ctx.accounts.escrow.key(),
-            quest_id: escrow.quest_id.clone(),
-            sponsor: ctx.accounts.sponsor.key(),
-            total_amount,
+        let balance = team_vault.debit(vault_token_account.mint, payout.amount)?;
+        proposal.skipped_payouts &= !(1 << payout_index);
+
+        emit!(VaultWithdrawal {
+            team_vault: team_vault.key(),
+            proposal: proposal.key(),
+            recipient: payout.recipient,
+            mint: vault_token_account.mint,
+            amount: payout.amount,
+            balance,
         });
 
         Ok(())
     }
 
-    /// Release milestone payment from escrow
-    pub fn release_milestone(
-        ctx: Context<ReleaseMilestone>,
-        milestone_index: u8,
-    ) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
+    /// Mark a pending proposal as expired once its voting period has passed, or
+    /// an approved one once its execution window has
+    pub fn expire_proposal(ctx: Context<ExpireProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
         
         require!(
-            escrow.status == EscrowStatus::Active,
-            ErrorCode::EscrowNotActive
+            proposal.status == ProposalStatus::Pending || proposal.status == ProposalStatus::Approved,
+            ErrorCode::ProposalNotActive
         );
         
         require!(
-            milestone_index < escrow.milestones.len() as u8,
-            ErrorCode::InvalidMilestoneIndex
+            Clock::get()?.unix_timestamp >= proposal.expires_at,
+            ErrorCode::ProposalNotExpired
         );
+
+        proposal.status = ProposalStatus::Expired;
+
+        emit!(ProposalExpired {
+            proposal: proposal.key(),
+            team_vault: proposal.team_vault,
+            expires_at: proposal.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a proposal before any founder has voted on it
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
         
-        let milestone = &mut escrow.milestones[milestone_index as usize];
+        require!(
+            proposal.status == ProposalStatus::Pending,
+            ErrorCode::ProposalNotActive
+        );
         
         require!(
-            !milestone.released,
-            ErrorCode::MilestoneAlreadyReleased
+            proposal.votes.is_empty(),
+            ErrorCode::ProposalHasVotes
         );
 
-        // Calculate release amount
-        let release_amount = (escrow.total_amount as u128 * milestone.percentage as u128 / 100) as u64;
+        proposal.status = ProposalStatus::Cancelled;
+
+        emit!(ProposalCancelled {
+            proposal: proposal.key(),
+            team_vault: proposal.team_vault,
+            proposer: proposal.proposer,
+        });
+
+        Ok(())
+    }
+
+    /// Spend from a founder's per-period allowance without a proposal
+    pub fn spend_from_allowance(
+        ctx: Context<SpendFromAllowance>,
+        amount: u64,
+    ) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let founder = ctx.accounts.founder.key();
+        let mint = ctx.accounts.vault_token_account.mint;
+
+        require!(
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
+        );
+
+        // A freeze stops allowance spending just like proposal execution
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            !team_vault.is_frozen(now),
+            ErrorCode::VaultFrozenByDispute
+        );
+
+        require!(
+            amount > 0,
+            ErrorCode::InvalidAmount
+        );
+
+        let allowance = team_vault
+            .allowances
+            .iter_mut()
+            .find(|allowance| allowance.founder == founder && allowance.mint == mint)
+            .ok_or(ErrorCode::AllowanceNotFound)?;
+        allowance.debit(amount, now)?;
+        let remaining = allowance.amount_per_period - allowance.spent;
 
-        // Transfer funds from escrow to quest creator
         let seeds = &[
-            b"sponsor_escrow",
-            escrow.quest_id.as_bytes(),
-            &[escrow.bump],
+            b"team_vault",
+            team_vault.team_id.as_bytes(),
+            &[team_vault.bump],
         ];
         let signer = &[&seeds[..]];
 
@@ -291,406 +1100,4862 @@ pub mod rise_of_founders_protocol {
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    from: ctx.accounts.vault_token_account.to_account_info(),
                     to: ctx.accounts.recipient_token_account.to_account_info(),
-                    authority: ctx.accounts.escrow.to_account_info(),
+                    authority: team_vault.to_account_info(),
                 },
                 signer,
             ),
-            release_amount,
+            amount,
         )?;
 
-        milestone.released = true;
-        milestone.released_at = Some(Clock::get()?.unix_timestamp);
-        escrow.released_amount += release_amount;
-
-        // Check if all milestones are complete
-        if escrow.milestones.iter().all(|m| m.released) {
-            escrow.status = EscrowStatus::Completed;
-        }
+        let balance = team_vault.debit(mint, amount)?;
 
-        emit!(MilestoneReleased {
-            escrow: ctx.accounts.escrow.key(),
-            milestone_index,
-            amount: release_amount,
+        emit!(AllowanceSpent {
+            team_vault: team_vault.key(),
+            founder,
+            mint,
+            recipient: ctx.accounts.recipient_token_account.owner,
+            amount,
+            remaining,
+            balance,
         });
 
         Ok(())
     }
 
-    // =============================================================================
-    // TERRITORY NFT PROGRAM
-    // =============================================================================
-
-    /// Initialize a new territory NFT
-    pub fn initialize_territory(
-        ctx: Context<InitializeTerritory>,
-        territory_id: String,
-        name: String,
-        description: String,
-        coordinates: [i32; 2], // [x, y]
-        size: u32,
-        difficulty: u8,
-        max_teams: u16,
-        uri: String,
-        bump: u8,
+    /// Let another founder or an outside key vote on a founder's behalf until expiry
+    pub fn delegate_vote(
+        ctx: Context<ManageDelegation>,
+        delegate: Pubkey,
+        expires_at: i64,
     ) -> Result<()> {
-        let territory = &mut ctx.accounts.territory;
-        
+        let team_vault = &mut ctx.accounts.team_vault;
+        let founder = ctx.accounts.founder.key();
+
         require!(
-            difficulty >= 1 && difficulty <= 5,
-            ErrorCode::InvalidDifficulty
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
         );
 
-        territory.territory_id = territory_id;
-        territory.name = name;
-        territory.description = description;
-        territory.coordinates = coordinates;
-        territory.size = size;
-        territory.difficulty = difficulty;
-        territory.max_teams = max_teams;
-        territory.current_teams = 0;
-        territory.uri = uri;
-        territory.owner = None;
-        territory.battles_won = 0;
-        territory.battles_lost = 0;
-        territory.total_rewards = 0;
-        territory.is_active = true;
-        territory.bump = bump;
-        territory.created_at = Clock::get()?.unix_timestamp;
+        require!(
+            team_vault.founders.contains(&founder),
+            ErrorCode::UnauthorizedFounder
+        );
 
-        emit!(TerritoryCreated {
-            territory: ctx.accounts.territory.key(),
-            territory_id: territory.territory_id.clone(),
-            name: territory.name.clone(),
-            coordinates,
-            difficulty,
+        require!(
+            delegate != founder,
+            ErrorCode::InvalidDelegation
+        );
+
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidDelegation
+        );
+
+        // A new delegation replaces the founder's previous one
+        team_vault.delegations.retain(|delegation| delegation.founder != founder);
+        team_vault.delegations.push(VoteDelegation {
+            founder,
+            delegate,
+            expires_at,
+        });
+
+        emit!(VoteDelegated {
+            team_vault: team_vault.key(),
+            founder,
+            delegate,
+            expires_at,
         });
 
         Ok(())
     }
 
-    /// Initiate a battle for territory control
-    pub fn challenge_territory(
-        ctx: Context<ChallengeTerritory>,
-        challenger_team_id: String,
-        battle_type: BattleType,
+    /// Revoke a founder's vote delegation
+    pub fn revoke_delegation(ctx: Context<ManageDelegation>) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let founder = ctx.accounts.founder.key();
+
+        let index = team_vault
+            .delegations
+            .iter()
+            .position(|delegation| delegation.founder == founder)
+            .ok_or(ErrorCode::DelegationNotFound)?;
+        let delegation = team_vault.delegations.remove(index);
+
+        emit!(VoteDelegationRevoked {
+            team_vault: team_vault.key(),
+            founder,
+            delegate: delegation.delegate,
+        });
+
+        Ok(())
+    }
+
+    /// Move a founder's seat to a new key, signed by both the old and new keys.
+    /// Open proposals become stale, as with any other change to the founder set;
+    /// the founder's vesting and votes on open disputes (passed as remaining
+    /// accounts) move to the new key.
+    pub fn rotate_founder_key<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RotateFounderKey<'info>>,
     ) -> Result<()> {
-        let territory = &ctx.accounts.territory;
-        let battle = &mut ctx.accounts.battle;
-        
+        let team_vault = &mut ctx.accounts.team_vault;
+        let old_founder = ctx.accounts.founder.key();
+        let new_founder = ctx.accounts.new_founder.key();
+
         require!(
-            territory.is_active,
-            ErrorCode::TerritoryNotActive
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
         );
-        
-        // Create battle record
-        battle.territory = ctx.accounts.territory.key();
-        battle.challenger = ctx.accounts.challenger.key();
-        battle.challenger_team_id = challenger_team_id;
-        battle.defender = territory.owner;
-        battle.battle_type = battle_type;
-        battle.status = BattleStatus::Pending;
-        battle.stake_amount = 0; // To be set based on battle type
-        battle.created_at = Clock::get()?.unix_timestamp;
-        battle.expires_at = Clock::get()?.unix_timestamp + 24 * 60 * 60; // 24 hours
 
-        emit!(TerritoryChallenge {
-            territory: ctx.accounts.territory.key(),
-            battle: ctx.accounts.battle.key(),
-            challenger: ctx.accounts.challenger.key(),
-            battle_type,
+        move_founder_records(
+            team_vault,
+            ctx.accounts.founder_vesting.as_mut(),
+            ctx.remaining_accounts,
+            &old_founder,
+            new_founder,
+        )?;
+        team_vault.replace_founder(&old_founder, new_founder)?;
+        team_vault.config_version = team_vault
+            .config_version
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(FounderKeyRotated {
+            team_vault: team_vault.key(),
+            old_founder,
+            new_founder,
+            recovered: false,
         });
 
         Ok(())
     }
 
-    /// Resolve a territory battle
-    pub fn resolve_battle(
-        ctx: Context<ResolveBattle>,
-        winner: Pubkey,
-        score: u32,
+    /// Distribute a vault token account left after a separation settled, such
+    /// as one for a mint the vault never tracked, by the separation's shares.
+    /// Founders' token accounts are passed in distribution order as remaining
+    /// accounts.
+    pub fn sweep_separated_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SweepSeparatedVault<'info>>,
     ) -> Result<()> {
-        let territory = &mut ctx.accounts.territory;
-        let battle = &mut ctx.accounts.battle;
-        
+        let proposal = &ctx.accounts.proposal;
+        let team_vault = &mut ctx.accounts.team_vault;
+
+        let distribution = match &proposal.proposal_type {
+            ProposalType::SeparateTeam { distribution } => distribution.clone(),
+            _ => return err!(ErrorCode::TeamVaultNotSettled),
+        };
         require!(
-            battle.status == BattleStatus::Pending,
-            ErrorCode::BattleNotActive
+            !team_vault.is_active && proposal.status == ProposalStatus::Executed,
+            ErrorCode::TeamVaultNotSettled
         );
 
-        battle.status = BattleStatus::Completed;
-        battle.winner = Some(winner);
-        battle.score = score;
-        battle.resolved_at = Some(Clock::get()?.unix_timestamp);
+        require!(
+            !team_vault.closed,
+            ErrorCode::TeamVaultAlreadyClosed
+        );
 
-        // Update territory ownership if challenger wins
-        if winner == battle.challenger {
-            territory.owner = Some(battle.challenger);
-            territory.battles_won += 1;
-        } else {
-            territory.battles_lost += 1;
-        }
+        let balance = distribute_vault_token_account(
+            team_vault,
+            &mut ctx.accounts.vault_token_account,
+            &distribution,
+            ctx.remaining_accounts,
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
 
-        emit!(BattleResolved {
-            territory: ctx.accounts.territory.key(),
-            battle: ctx.accounts.battle.key(),
-            winner,
-            score,
+        emit!(SeparationDistribution {
+            team_vault: team_vault.key(),
+            proposal: proposal.key(),
+            mint: ctx.accounts.vault_token_account.mint,
+            amount: balance,
         });
 
         Ok(())
     }
-}
 
-// =============================================================================
-// ACCOUNT STRUCTURES
-// =============================================================================
+    /// Mark a separated, fully drained team vault closed. The account shrinks to
+    /// a tombstone so nobody can register its team id again and claim tokens
+    /// that later reach the vault address; the rent it no longer needs is
+    /// split evenly between the founders, passed in order as remaining
+    /// accounts.
+    pub fn close_team_vault(ctx: Context<CloseTeamVault>) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
 
-#[account]
-pub struct TeamVault {
-    pub team_id: String,
-    pub name: String,
-    pub founders: Vec<Pubkey>,
-    pub threshold: u8,
-    pub total_funds: u64,
-    pub proposal_count: u64,
-    pub bump: u8,
-    pub created_at: i64,
-    pub is_active: bool,
-}
+        require!(
+            team_vault.founders.contains(&ctx.accounts.founder.key()),
+            ErrorCode::UnauthorizedFounder
+        );
 
-#[account]
-pub struct Proposal {
-    pub team_vault: Pubkey,
-    pub proposer: Pubkey,
-    pub title: String,
-    pub description: String,
-    pub recipient: Pubkey,
-    pub amount: u64,
-    pub proposal_type: ProposalType,
-    pub votes: Vec<Vote>,
-    pub status: ProposalStatus,
-    pub created_at: i64,
-    pub expires_at: i64,
-    pub executed_at: Option<i64>,
-}
+        require!(
+            !team_vault.is_active
+                && team_vault.balances.is_empty()
+                && team_vault.vesting_founders.is_empty(),
+            ErrorCode::TeamVaultNotSettled
+        );
 
-#[account]
-pub struct SponsorEscrow {
-    pub quest_id: String,
-    pub sponsor: Pubkey,
-    pub total_amount: u64,
-    pub released_amount: u64,
-    pub milestones: Vec<Milestone>,
-    pub status: EscrowStatus,
-    pub bump: u8,
-    pub created_at: i64,
-}
+        require!(
+            !team_vault.closed,
+            ErrorCode::TeamVaultAlreadyClosed
+        );
 
-#[account]
-pub struct Territory {
-    pub territory_id: String,
-    pub name: String,
-    pub description: String,
-    pub coordinates: [i32; 2],
-    pub size: u32,
-    pub difficulty: u8,
-    pub max_teams: u16,
-    pub current_teams: u16,
-    pub uri: String,
-    pub owner: Option<Pubkey>,
-    pub battles_won: u32,
-    pub battles_lost: u32,
-    pub total_rewards: u64,
-    pub is_active: bool,
-    pub bump: u8,
-    pub created_at: i64,
-}
+        require!(
+            ctx.remaining_accounts.len() == team_vault.founders.len()
+                && ctx
+                    .remaining_accounts
+                    .iter()
+                    .zip(team_vault.founders.iter())
+                    .all(|(account, founder)| account.key == founder),
+            ErrorCode::MissingFounderAccounts
+        );
 
-#[account]
-pub struct Battle {
-    pub territory: Pubkey,
-    pub challenger: Pubkey,
-    pub challenger_team_id: String,
-    pub defender: Option<Pubkey>,
-    pub battle_type: BattleType,
-    pub status: BattleStatus,
-    pub stake_amount: u64,
-    pub winner: Option<Pubkey>,
-    pub score: u32,
-    pub created_at: i64,
-    pub expires_at: i64,
-    pub resolved_at: Option<i64>,
-}
+        team_vault.retire();
 
-// =============================================================================
-// DATA TYPES
-// =============================================================================
+        let vault_info = team_vault.to_account_info();
+        let space = 8 + team_vault.try_to_vec()?.len();
+        vault_info.realloc(space, false)?;
+        let refunded = vault_info
+            .lamports()
+            .checked_sub(Rent::get()?.minimum_balance(space))
+            .ok_or(ErrorCode::MathOverflow)?;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct Vote {
-    pub voter: Pubkey,
-    pub support: bool,
-    pub timestamp: i64,
-}
+        // The last founder takes the rounding dust
+        let founder_count = ctx.remaining_accounts.len() as u64;
+        for (index, founder_info) in ctx.remaining_accounts.iter().enumerate() {
+            let share = if index == ctx.remaining_accounts.len() - 1 {
+                refunded - refunded / founder_count * (founder_count - 1)
+            } else {
+                refunded / founder_count
+            };
+            **vault_info.try_borrow_mut_lamports()? -= share;
+            **founder_info.try_borrow_mut_lamports()? = founder_info
+                .lamports()
+                .checked_add(share)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct Milestone {
-    pub title: String,
-    pub description: String,
-    pub percentage: u16, // Percentage of total amount (0-100)
-    pub released: bool,
-    pub released_at: Option<i64>,
-}
+        emit!(TeamVaultClosed {
+            team_vault: team_vault.key(),
+            closed_by: ctx.accounts.founder.key(),
+            refunded,
+        });
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum ProposalType {
-    Transfer,
-}
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum ProposalStatus {
-    Pending,
-    Approved,
-    Rejected,
+    /// Claim a founder's vested tokens from their vesting account
+    pub fn claim_vested_tokens(ctx: Context<ClaimVestedTokens>) -> Result<()> {
+        let founder_vesting = &mut ctx.accounts.founder_vesting;
+
+        let vested = founder_vesting.vested_amount(Clock::get()?.unix_timestamp)?;
+        let amount = vested
+            .checked_sub(founder_vesting.claimed)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        require!(
+            amount > 0,
+            ErrorCode::NothingToWithdraw
+        );
+
+        let (team_vault_key, founder) = (founder_vesting.team_vault, founder_vesting.founder);
+        let seeds = &[
+            b"founder_vesting",
+            team_vault_key.as_ref(),
+            founder.as_ref(),
+            &[founder_vesting.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vesting_token_account.to_account_info(),
+                    to: ctx.accounts.founder_token_account.to_account_info(),
+                    authority: founder_vesting.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        founder_vesting.claimed = vested;
+
+        emit!(VestedTokensClaimed {
+            founder_vesting: founder_vesting.key(),
+            team_vault: founder_vesting.team_vault,
+            founder: founder_vesting.founder,
+            amount,
+            claimed: founder_vesting.claimed,
+        });
+
+        // Once nothing is left to vest or claim the vesting closes; stray
+        // tokens go to the founder along with the final claim
+        if founder_vesting.vested_amount(i64::MAX)? == founder_vesting.claimed {
+            let stray = ctx.accounts.vesting_token_account.amount.saturating_sub(amount);
+            if stray > 0 {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.vesting_token_account.to_account_info(),
+                            to: ctx.accounts.founder_token_account.to_account_info(),
+                            authority: founder_vesting.to_account_info(),
+                        },
+                        signer,
+                    ),
+                    stray,
+                )?;
+            }
+
+            close_founder_vesting(
+                founder_vesting,
+                &ctx.accounts.vesting_token_account,
+                ctx.accounts.founder.to_account_info(),
+                &ctx.accounts.token_program,
+            )?;
+            let beneficiary = founder_vesting.beneficiary;
+            ctx.accounts.team_vault.vesting_founders.retain(|f| *f != beneficiary);
+        }
+
+        Ok(())
+    }
+
+    /// Withdraw everything vested so far from a payment stream
+    pub fn withdraw_from_stream(ctx: Context<WithdrawFromStream>) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let payment_stream = &mut ctx.accounts.payment_stream;
+
+        let vested = payment_stream.vested_amount(Clock::get()?.unix_timestamp)?;
+        let amount = vested
+            .checked_sub(payment_stream.withdrawn)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        require!(
+            amount > 0,
+            ErrorCode::NothingToWithdraw
+        );
+
+        let seeds = &[
+            b"team_vault",
+            team_vault.team_id.as_bytes(),
+            &[team_vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: team_vault.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        payment_stream.withdrawn = vested;
+        let balance = team_vault.debit(payment_stream.mint, amount)?;
+
+        emit!(PaymentStreamWithdrawal {
+            payment_stream: payment_stream.key(),
+            team_vault: team_vault.key(),
+            recipient: payment_stream.recipient,
+            mint: payment_stream.mint,
+            amount,
+            withdrawn: payment_stream.withdrawn,
+            balance,
+        });
+
+        Ok(())
+    }
+
+    /// Open a dispute between founders, optionally freezing proposal execution
+    /// until its voting deadline
+    pub fn open_dispute(
+        ctx: Context<OpenDispute>,
+        dispute_type: DisputeType,
+        evidence_hash: [u8; 32],
+        freeze_vault: bool,
+    ) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let dispute = &mut ctx.accounts.dispute;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            team_vault.is_active,
+            ErrorCode::TeamVaultNotActive
+        );
+
+        require!(
+            team_vault.founders.contains(&ctx.accounts.initiator.key()),
+            ErrorCode::UnauthorizedFounder
+        );
+
+        dispute.team_vault = team_vault.key();
+        dispute.index = team_vault.dispute_count;
+        dispute.initiator = ctx.accounts.initiator.key();
+        dispute.dispute_type = dispute_type.clone();
+        dispute.evidence_hash = evidence_hash;
+        dispute.freezes_vault = freeze_vault;
+        dispute.arbitrator = team_vault.arbitrator.ok_or(ErrorCode::NoArbitrator)?;
+        dispute.votes = Vec::new();
+        dispute.status = DisputeStatus::Open;
+        dispute.resolved_by_arbitrator = false;
+        dispute.created_at = now;
+        dispute.voting_deadline = now
+            .checked_add(team_vault.governance.voting_period)
+            .ok_or(ErrorCode::MathOverflow)?;
+        dispute.resolved_at = None;
+        dispute.bump = ctx.bumps.dispute;
+
+        team_vault.dispute_count = team_vault
+            .dispute_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        team_vault.open_disputes = team_vault
+            .open_disputes
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if freeze_vault {
+            // The freeze lifts at the voting deadline even while the dispute
+            // awaits arbitration
+            team_vault.freeze(dispute.initiator, dispute.voting_deadline, now)?;
+        }
+
+        emit!(DisputeOpened {
+            dispute: dispute.key(),
+            team_vault: team_vault.key(),
+            initiator: dispute.initiator,
+            dispute_type,
+            evidence_hash,
+            freezes_vault: freeze_vault,
+            voting_deadline: dispute.voting_deadline,
+        });
+
+        Ok(())
+    }
+
+    /// Vote to uphold or dismiss an open dispute
+    pub fn vote_on_dispute(
+        ctx: Context<VoteOnDispute>,
+        support: bool,
+    ) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let dispute = &mut ctx.accounts.dispute;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            team_vault.founders.contains(&ctx.accounts.voter.key()),
+            ErrorCode::UnauthorizedFounder
+        );
+
+        require!(
+            !dispute.votes.iter().any(|vote| vote.voter == ctx.accounts.voter.key()),
+            ErrorCode::AlreadyVoted
+        );
+
+        require!(
+            dispute.status == DisputeStatus::Open,
+            ErrorCode::DisputeNotOpen
+        );
+
+        require!(
+            now < dispute.voting_deadline,
+            ErrorCode::DisputeVotingClosed
+        );
+
+        dispute.votes.push(Vote {
+            voter: ctx.accounts.voter.key(),
+            delegator: None,
+            support,
+            timestamp: now,
+        });
+
+        // Tally with the same weights and threshold as proposals; founders
+        // removed since voting simply carry no weight
+        let (support_weight, against_weight) = dispute.votes.iter().fold(
+            (0u64, 0u64),
+            |(support_weight, against_weight), vote| {
+                let weight = team_vault.vote_weight(&vote.voter);
+                if vote.support {
+                    (support_weight + weight, against_weight)
+                } else {
+                    (support_weight, against_weight + weight)
+                }
+            },
+        );
+        let required_weight = team_vault.required_weight();
+        if support_weight >= required_weight {
+            dispute.resolve(team_vault, DisputeStatus::Approved, false, now);
+        } else if team_vault.total_weight().saturating_sub(against_weight) < required_weight {
+            dispute.resolve(team_vault, DisputeStatus::Rejected, false, now);
+        } else {
+            return Ok(());
+        }
+
+        emit!(DisputeResolved {
+            dispute: dispute.key(),
+            team_vault: team_vault.key(),
+            status: dispute.status.clone(),
+            resolved_by_arbitrator: false,
+        });
+
+        Ok(())
+    }
+
+    /// Let the named arbitrator settle a dispute the founders left unresolved
+    pub fn arbitrate_dispute(
+        ctx: Context<ArbitrateDispute>,
+        uphold: bool,
+    ) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let dispute = &mut ctx.accounts.dispute;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            dispute.arbitrator == ctx.accounts.arbitrator.key(),
+            ErrorCode::UnauthorizedArbitrator
+        );
+
+        require!(
+            dispute.status == DisputeStatus::Open,
+            ErrorCode::DisputeNotOpen
+        );
+
+        require!(
+            now >= dispute.voting_deadline,
+            ErrorCode::DisputeVotingOpen
+        );
+
+        let status = if uphold {
+            DisputeStatus::Approved
+        } else {
+            DisputeStatus::Rejected
+        };
+        dispute.resolve(team_vault, status, true, now);
+
+        emit!(DisputeResolved {
+            dispute: dispute.key(),
+            team_vault: team_vault.key(),
+            status: dispute.status.clone(),
+            resolved_by_arbitrator: true,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a dispute before it is resolved
+    pub fn cancel_dispute(ctx: Context<CancelDispute>) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let dispute = &mut ctx.accounts.dispute;
+
+        require!(
+            dispute.initiator == ctx.accounts.initiator.key(),
+            ErrorCode::UnauthorizedFounder
+        );
+
+        require!(
+            dispute.status == DisputeStatus::Open,
+            ErrorCode::DisputeNotOpen
+        );
+
+        dispute.resolve(team_vault, DisputeStatus::Cancelled, false, Clock::get()?.unix_timestamp);
+
+        emit!(DisputeCancelled {
+            dispute: dispute.key(),
+            team_vault: team_vault.key(),
+            initiator: dispute.initiator,
+        });
+
+        Ok(())
+    }
+
+    /// Anchor a founder agreement document on-chain for signing
+    pub fn create_founder_agreement(
+        ctx: Context<CreateFounderAgreement>,
+        team_id: String,
+        version: u32,
+        document_hash: [u8; 32],
+        founders: Vec<Pubkey>,
+    ) -> Result<()> {
+        let founder_agreement = &mut ctx.accounts.founder_agreement;
+
+        require!(
+            version > 0,
+            ErrorCode::InvalidAgreement
+        );
+
+        require!(
+            !team_id.is_empty() && team_id.len() <= MAX_ID_LEN,
+            ErrorCode::InvalidTeamId
+        );
+
+        require!(
+            !founders.is_empty() && founders.len() <= MAX_FOUNDERS,
+            ErrorCode::TooManyFounders
+        );
+
+        require!(
+            founders.iter().enumerate().all(|(index, founder)| !founders[..index].contains(founder)),
+            ErrorCode::InvalidAgreement
+        );
+
+        require!(
+            founders.contains(&ctx.accounts.creator.key()),
+            ErrorCode::UnauthorizedFounder
+        );
+
+        founder_agreement.team_id = team_id;
+        founder_agreement.creator = ctx.accounts.creator.key();
+        founder_agreement.version = version;
+        founder_agreement.document_hash = document_hash;
+        founder_agreement.founders = founders;
+        founder_agreement.signatures = Vec::new();
+        founder_agreement.status = AgreementStatus::PendingSignatures;
+        founder_agreement.created_at = Clock::get()?.unix_timestamp;
+        founder_agreement.fully_signed_at = None;
+        founder_agreement.bump = ctx.bumps.founder_agreement;
+
+        emit!(AgreementCreated {
+            founder_agreement: founder_agreement.key(),
+            team_id: founder_agreement.team_id.clone(),
+            version,
+            document_hash,
+            founders: founder_agreement.founders.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Record a founder's signature on an agreement
+    pub fn sign_agreement(ctx: Context<SignAgreement>) -> Result<()> {
+        let founder_agreement = &mut ctx.accounts.founder_agreement;
+        let founder = ctx.accounts.founder.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            founder_agreement.status == AgreementStatus::PendingSignatures,
+            ErrorCode::AgreementNotPending
+        );
+
+        require!(
+            founder_agreement.founders.contains(&founder),
+            ErrorCode::UnauthorizedFounder
+        );
+
+        require!(
+            !founder_agreement.signatures.iter().any(|signature| signature.founder == founder),
+            ErrorCode::AlreadySigned
+        );
+
+        founder_agreement.signatures.push(AgreementSignature {
+            founder,
+            signed_at: now,
+        });
+
+        emit!(AgreementSigned {
+            founder_agreement: founder_agreement.key(),
+            founder,
+            signatures: founder_agreement.signatures.len() as u8,
+        });
+
+        if founder_agreement.signatures.len() == founder_agreement.founders.len() {
+            founder_agreement.status = AgreementStatus::FullySigned;
+            founder_agreement.fully_signed_at = Some(now);
+
+            emit!(AgreementFullySigned {
+                founder_agreement: founder_agreement.key(),
+                team_id: founder_agreement.team_id.clone(),
+                version: founder_agreement.version,
+                document_hash: founder_agreement.document_hash,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Adopt a newer fully signed agreement version for an existing team vault
+    pub fn adopt_agreement(ctx: Context<AdoptAgreement>) -> Result<()> {
+        let team_vault = &mut ctx.accounts.team_vault;
+        let founder_agreement = &ctx.accounts.founder_agreement;
+
+        require!(
+            founder_agreement.status == AgreementStatus::FullySigned,
+            ErrorCode::AgreementNotFullySigned
+        );
+
+        require!(
+            founder_agreement.team_id == team_vault.team_id
+                && same_founders(&founder_agreement.founders, &team_vault.founders),
+            ErrorCode::AgreementMismatch
+        );
+
+        require!(
+            founder_agreement.version > team_vault.agreement_version,
+            ErrorCode::AgreementVersionOutdated
+        );
+
+        team_vault.agreement_version = founder_agreement.version;
+        team_vault.agreement_required = true;
+        team_vault.agreement_current = true;
+
+        emit!(AgreementAdopted {
+            team_vault: team_vault.key(),
+            founder_agreement: founder_agreement.key(),
+            version: founder_agreement.version,
+        });
+
+        Ok(())
+    }
+
+    // =============================================================================
+    // SPONSOR ESCROW PROGRAM
+    // =============================================================================
+
+    /// Initialize a sponsor escrow for quest funding
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_sponsor_escrow(
+        ctx: Context<InitializeSponsorEscrow>,
+        quest_id: String,
+        total_amount: u64,
+        milestones: Vec<Milestone>,
+        judges: Vec<Pubkey>,
+        judge_threshold: u8,
+        recipients: Vec<Pubkey>,
+        prize_table: PrizeTable,
+        min_contribution: u64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        
+        require!(
+            !quest_id.is_empty() && quest_id.len() <= MAX_ID_LEN,
+            ErrorCode::InvalidQuestId
+        );
+        
+        require!(
+            milestones.len() <= MAX_MILESTONES,
+            ErrorCode::TooManyMilestones
+        );
+        
+        require!(
+            milestones.iter().all(|m| m.title.len() <= MAX_MILESTONE_TITLE_LEN),
+            ErrorCode::MilestoneTitleTooLong
+        );
+        
+        require!(
+            milestones.iter().all(|m| m.description.len() <= MAX_DESCRIPTION_LEN),
+            ErrorCode::DescriptionTooLong
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            milestones.iter().all(|m| m.deadline.unwrap_or(i64::MAX) > now),
+            ErrorCode::InvalidMilestoneDeadline
+        );
+        
+        prize_table.validate(total_amount, now)?;
+        
+        // Fixed amounts come off the milestone pool first and basis points
+        // split what is left; without basis points the fixed amounts must
+        // cover the pool exactly
+        let milestone_pool = total_amount
+            .checked_sub(prize_table.pool)
+            .ok_or(ErrorCode::InvalidPrizeTable)?;
+        let fixed_total = fixed_milestone_total(&milestones)?;
+        let total_bps = milestones
+            .iter()
+            .map(|m| match m.share {
+                MilestoneShare::Bps(bps) => bps as u32,
+                MilestoneShare::Amount(_) => 0,
+            })
+            .sum::<u32>();
+        require!(
+            if total_bps == 0 {
+                fixed_total == milestone_pool
+            } else {
+                total_bps == 10_000 && fixed_total <= milestone_pool
+            },
+            ErrorCode::InvalidMilestoneShares
+        );
+        
+        require!(
+            judges.len() <= MAX_JUDGES
+                && judges.iter().enumerate().all(|(index, judge)| !judges[..index].contains(judge)),
+            ErrorCode::InvalidJudges
+        );
+        
+        // Without judges the sponsor alone releases; with judges at least one
+        // approval is always required
+        require!(
+            (judge_threshold as usize) <= judges.len() && (judge_threshold == 0) == judges.is_empty(),
+            ErrorCode::InvalidJudgeThreshold
+        );
+        
+        require!(
+            !recipients.is_empty()
+                && recipients.len() <= MAX_RECIPIENTS
+                && recipients.iter().enumerate().all(|(index, recipient)| !recipients[..index].contains(recipient)),
+            ErrorCode::InvalidRecipients
+        );
+
+        escrow.quest_id = quest_id;
+        escrow.sponsor = ctx.accounts.sponsor.key();
+        escrow.mint = ctx.accounts.escrow_token_account.mint;
+        escrow.total_amount = total_amount;
+        escrow.released_amount = 0;
+        escrow.refunded_amount = 0;
+        escrow.reserved_amount = 0;
+        escrow.contributions = vec![Contribution {
+            contributor: ctx.accounts.sponsor.key(),
+            amount: total_amount,
+        }];
+        escrow.min_contribution = min_contribution;
+        escrow.owed_refunds = Vec::new();
+        // Milestones always start unsettled and unapproved
+        escrow.milestones = milestones
+            .into_iter()
+            .map(|milestone| Milestone {
+                released: false,
+                released_at: None,
+                refunded: false,
+                settled_amount: 0,
+                approvals: Vec::new(),
+                ..milestone
+            })
+            .collect();
+        escrow.judges = judges;
+        escrow.judge_threshold = judge_threshold;
+        escrow.recipients = recipients;
+        escrow.cancel_approvals = Vec::new();
+        escrow.prizes_settled = prize_table.rank_bps.is_empty();
+        escrow.prize_table = prize_table;
+        escrow.result_votes = Vec::new();
+        escrow.prizes = Vec::new();
+        escrow.results_finalized_at = None;
+        escrow.status = EscrowStatus::Active;
+        escrow.bump = ctx.bumps.escrow;
+        escrow.created_at = now;
+
+        // Transfer funds to escrow
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.sponsor_token_account.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.sponsor.to_account_info(),
+                },
+            ),
+            total_amount,
+        )?;
+
+        emit!(EscrowCreated {
+            escrow: escrow.key(),
+            quest_id: escrow.quest_id.clone(),
+            sponsor: ctx.accounts.sponsor.key(),
+            total_amount,
+            judges: escrow.judges.clone(),
+            judge_threshold: escrow.judge_threshold,
+            recipients: escrow.recipients.clone(),
+            prize_table: escrow.prize_table.clone(),
+            min_contribution,
+        });
+
+        Ok(())
+    }
+
+    /// Add funds from a co-sponsor; milestones still to be paid grow with the
+    /// new total
+    pub fn fund_escrow(
+        ctx: Context<FundEscrow>,
+        amount: u64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let contributor = ctx.accounts.contributor.key();
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            amount > 0,
+            ErrorCode::InvalidAmount
+        );
+        
+        // Funds only reach participants through a milestone still to be paid
+        // whose share grows with the total; fixed amounts never would
+        require!(
+            escrow
+                .milestones
+                .iter()
+                .any(|m| !m.released && !m.refunded && matches!(m.share, MilestoneShare::Bps(_))),
+            ErrorCode::NoOpenMilestones
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.contributor_token_account.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.contributor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let contributed = match escrow.contributions.iter_mut().find(|c| c.contributor == contributor) {
+            Some(contribution) => {
+                contribution.amount = contribution
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                contribution.amount
+            }
+            None => {
+                require!(
+                    escrow.contributions.len() < MAX_CONTRIBUTORS,
+                    ErrorCode::TooManyContributors
+                );
+                
+                // Keeps dust contributions from taking up the ledger
+                require!(
+                    amount >= escrow.min_contribution,
+                    ErrorCode::ContributionTooSmall
+                );
+                escrow.contributions.push(Contribution { contributor, amount });
+                amount
+            }
+        };
+        escrow.total_amount = escrow
+            .total_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(EscrowFunded {
+            escrow: escrow.key(),
+            contributor,
+            amount,
+            contributed,
+            total_amount: escrow.total_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Record a judge's attestation that a milestone was completed
+    pub fn approve_milestone(
+        ctx: Context<JudgeEscrow>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let judge = ctx.accounts.judge.key();
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            escrow.judges.contains(&judge),
+            ErrorCode::UnauthorizedJudge
+        );
+        
+        require!(
+            milestone_index < escrow.milestones.len() as u8,
+            ErrorCode::InvalidMilestoneIndex
+        );
+        
+        let milestone = &mut escrow.milestones[milestone_index as usize];
+        
+        require!(
+            !milestone.released,
+            ErrorCode::MilestoneAlreadyReleased
+        );
+        
+        require!(
+            !milestone.refunded,
+            ErrorCode::MilestoneAlreadyRefunded
+        );
+        
+        require!(
+            !milestone.approvals.contains(&judge),
+            ErrorCode::MilestoneAlreadyApproved
+        );
+
+        milestone.approvals.push(judge);
+        let approvals = milestone.approvals.len() as u8;
+
+        emit!(MilestoneApproved {
+            escrow: escrow.key(),
+            milestone_index,
+            judge,
+            approvals,
+            judge_threshold: escrow.judge_threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Release milestone payment from escrow
+    pub fn release_milestone(
+        ctx: Context<ReleaseMilestone>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            milestone_index < escrow.milestones.len() as u8,
+            ErrorCode::InvalidMilestoneIndex
+        );
+        
+        let milestone = &escrow.milestones[milestone_index as usize];
+        
+        require!(
+            !milestone.released,
+            ErrorCode::MilestoneAlreadyReleased
+        );
+        
+        require!(
+            !milestone.refunded,
+            ErrorCode::MilestoneAlreadyRefunded
+        );
+        
+        require!(
+            milestone.approvals.len() >= escrow.judge_threshold as usize,
+            ErrorCode::MilestoneNotApproved
+        );
+
+        let release_amount = escrow.milestone_amount(milestone_index as usize)?;
+
+        // Transfer funds from escrow to quest creator
+        let seeds = &[
+            b"sponsor_escrow",
+            escrow.quest_id.as_bytes(),
+            &[escrow.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer,
+            ),
+            release_amount,
+        )?;
+
+        let milestone = &mut escrow.milestones[milestone_index as usize];
+        milestone.released = true;
+        milestone.released_at = Some(Clock::get()?.unix_timestamp);
+        milestone.settled_amount = release_amount;
+        escrow.released_amount = escrow
+            .released_amount
+            .checked_add(release_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if escrow.is_settled() {
+            escrow.status = EscrowStatus::Completed;
+        }
+
+        emit!(MilestoneReleased {
+            escrow: escrow.key(),
+            milestone_index,
+            recipient: ctx.accounts.recipient_token_account.owner,
+            amount: release_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Commit an approved milestone to a merkle root of (claimant, amount)
+    /// leaves so many participants can claim their share instead of the
+    /// milestone paying a single recipient
+    pub fn distribute_milestone(
+        ctx: Context<DistributeMilestone>,
+        milestone_index: u8,
+        merkle_root: [u8; 32],
+        claimant_count: u32,
+        expires_at: i64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let distribution = &mut ctx.accounts.distribution;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            milestone_index < escrow.milestones.len() as u8,
+            ErrorCode::InvalidMilestoneIndex
+        );
+        
+        let milestone = &escrow.milestones[milestone_index as usize];
+        
+        require!(
+            !milestone.released,
+            ErrorCode::MilestoneAlreadyReleased
+        );
+        
+        require!(
+            !milestone.refunded,
+            ErrorCode::MilestoneAlreadyRefunded
+        );
+        
+        require!(
+            milestone.approvals.len() >= escrow.judge_threshold as usize,
+            ErrorCode::MilestoneNotApproved
+        );
+        
+        require!(
+            claimant_count > 0 && claimant_count <= MAX_DISTRIBUTION_CLAIMANTS,
+            ErrorCode::InvalidMilestoneDistribution
+        );
+        
+        require!(
+            expires_at > now,
+            ErrorCode::InvalidMilestoneDistribution
+        );
+
+        let amount = escrow.milestone_amount(milestone_index as usize)?;
+
+        distribution.escrow = escrow.key();
+        distribution.milestone_index = milestone_index;
+        distribution.merkle_root = merkle_root;
+        distribution.total_amount = amount;
+        distribution.claimed_amount = 0;
+        distribution.claimant_count = claimant_count;
+        distribution.expires_at = expires_at;
+        distribution.claimed_bitmap = vec![0; DISTRIBUTION_BITMAP_LEN];
+        distribution.bump = ctx.bumps.distribution;
+
+        // The tokens stay in the escrow, reserved for claimants
+        let milestone = &mut escrow.milestones[milestone_index as usize];
+        milestone.released = true;
+        milestone.released_at = Some(now);
+        milestone.settled_amount = amount;
+        escrow.reserved_amount = escrow
+            .reserved_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(MilestoneDistributionCommitted {
+            escrow: escrow.key(),
+            distribution: distribution.key(),
+            milestone_index,
+            merkle_root,
+            claimant_count,
+            amount,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a participant's share of a milestone distribution with a merkle proof
+    pub fn claim_distribution(
+        ctx: Context<ClaimDistribution>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let distribution = &mut ctx.accounts.distribution;
+        let claimant = ctx.accounts.claimant.key();
+        
+        require!(
+            Clock::get()?.unix_timestamp < distribution.expires_at,
+            ErrorCode::DistributionExpired
+        );
+        
+        require!(
+            index < distribution.claimant_count,
+            ErrorCode::InvalidMerkleProof
+        );
+        
+        require!(
+            !distribution.is_claimed(index),
+            ErrorCode::DistributionAlreadyClaimed
+        );
+        
+        require!(
+            verify_merkle_proof(&proof, &distribution.merkle_root, distribution_leaf(index, &claimant, amount)),
+            ErrorCode::InvalidMerkleProof
+        );
+
+        // A bad root must not let claims dig into the rest of the escrow
+        let claimed_amount = distribution
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            claimed_amount <= distribution.total_amount,
+            ErrorCode::DistributionExceeded
+        );
+
+        let seeds = &[
+            b"sponsor_escrow",
+            escrow.quest_id.as_bytes(),
+            &[escrow.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: ctx.accounts.claimant_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        distribution.set_claimed(index);
+        distribution.claimed_amount = claimed_amount;
+        escrow.reserved_amount = escrow
+            .reserved_amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        escrow.released_amount = escrow
+            .released_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if escrow.status == EscrowStatus::Active && escrow.is_settled() {
+            escrow.status = EscrowStatus::Completed;
+        }
+
+        emit!(DistributionClaimed {
+            escrow: escrow.key(),
+            distribution: distribution.key(),
+            claimant,
+            index,
+            amount,
+            claimed_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return the unclaimed balance of an expired distribution to the
+    /// contributors and close it; a fully claimed distribution can be closed
+    /// right away
+    pub fn reclaim_distribution<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimDistribution<'info>>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let distribution = &ctx.accounts.distribution;
+        
+        require!(
+            Clock::get()?.unix_timestamp >= distribution.expires_at
+                || distribution.claimed_amount == distribution.total_amount,
+            ErrorCode::DistributionNotExpired
+        );
+
+        let amount = distribution
+            .total_amount
+            .checked_sub(distribution.claimed_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let refunds = refund_contributors(
+            escrow,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+            amount,
+        )?;
+
+        escrow.reserved_amount = escrow
+            .reserved_amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        escrow.refunded_amount = escrow
+            .refunded_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if escrow.status == EscrowStatus::Active && escrow.is_settled() {
+            escrow.status = EscrowStatus::Completed;
+        }
+
+        emit!(DistributionReclaimed {
+            escrow: escrow.key(),
+            distribution: distribution.key(),
+            amount,
+            refunds,
+        });
+
+        Ok(())
+    }
+
+    /// Record a judge's consent to cancelling the escrow
+    pub fn approve_cancellation(ctx: Context<JudgeEscrow>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let judge = ctx.accounts.judge.key();
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            escrow.judges.contains(&judge),
+            ErrorCode::UnauthorizedJudge
+        );
+        
+        require!(
+            !escrow.cancel_approvals.contains(&judge),
+            ErrorCode::CancellationAlreadyApproved
+        );
+
+        escrow.cancel_approvals.push(judge);
+
+        emit!(EscrowCancellationApproved {
+            escrow: escrow.key(),
+            judge,
+            approvals: escrow.cancel_approvals.len() as u8,
+            judge_threshold: escrow.judge_threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel the escrow with judge consent and refund everything not yet
+    /// released to the contributors, pro rata to what each put in. Judges who
+    /// let the results deadline pass forfeit their say, except over milestones
+    /// they already approved.
+    pub fn cancel_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelEscrow<'info>>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        let judges_consented = escrow.cancel_approvals.len() >= escrow.judge_threshold as usize;
+        require!(
+            judges_consented || escrow.results_overdue(now),
+            ErrorCode::CancellationNotApproved
+        );
+        
+        // Without consent, milestones the judges approved stay owed to the
+        // recipients
+        require!(
+            judges_consented
+                || escrow
+                    .milestones
+                    .iter()
+                    .enumerate()
+                    .all(|(index, m)| m.released || !escrow.is_judge_approved(index)),
+            ErrorCode::MilestoneAwaitingRelease
+        );
+        
+        // Winners are owed their prizes once results are final
+        require!(
+            escrow.results_finalized_at.is_none(),
+            ErrorCode::ResultsAlreadyFinalized
+        );
+
+        let refund_amount = escrow.remaining_amount()?;
+        let refunds = refund_contributors(
+            escrow,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+            refund_amount,
+        )?;
+
+        for milestone in escrow.milestones.iter_mut().filter(|m| !m.released) {
+            milestone.refunded = true;
+        }
+        escrow.refunded_amount = escrow
+            .refunded_amount
+            .checked_add(refund_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        escrow.status = EscrowStatus::Cancelled;
+
+        emit!(EscrowCancelled {
+            escrow: escrow.key(),
+            sponsor: escrow.sponsor,
+            refund_amount,
+            released_amount: escrow.released_amount,
+            refunds,
+        });
+
+        Ok(())
+    }
+
+    /// Refund an unreleased milestone to the contributors once its deadline
+    /// has passed
+    pub fn reclaim_expired_milestone<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimToContributors<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            milestone_index < escrow.milestones.len() as u8,
+            ErrorCode::InvalidMilestoneIndex
+        );
+        
+        let milestone = &escrow.milestones[milestone_index as usize];
+        
+        require!(
+            !milestone.released,
+            ErrorCode::MilestoneAlreadyReleased
+        );
+        
+        require!(
+            !milestone.refunded,
+            ErrorCode::MilestoneAlreadyRefunded
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            milestone.deadline.is_some_and(|deadline| now >= deadline),
+            ErrorCode::MilestoneNotExpired
+        );
+        
+        require!(
+            !escrow.is_judge_approved(milestone_index as usize),
+            ErrorCode::MilestoneAwaitingRelease
+        );
+
+        let refund_amount = escrow.milestone_amount(milestone_index as usize)?;
+        let refunds = refund_contributors(
+            escrow,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+            refund_amount,
+        )?;
+
+        let milestone = &mut escrow.milestones[milestone_index as usize];
+        milestone.refunded = true;
+        milestone.settled_amount = refund_amount;
+        escrow.refunded_amount = escrow
+            .refunded_amount
+            .checked_add(refund_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if escrow.is_settled() {
+            escrow.status = EscrowStatus::Completed;
+        }
+
+        emit!(MilestoneRefunded {
+            escrow: escrow.key(),
+            milestone_index,
+            refunds,
+            amount: refund_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Submit the ranked winner list; once enough judges submit the same list
+    /// the prize pool is allocated by rank. Escrows without judges take the
+    /// sponsor's list directly
+    pub fn finalize_results(
+        ctx: Context<JudgeEscrow>,
+        winners: Vec<Pubkey>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let judge = ctx.accounts.judge.key();
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        require!(
+            !escrow.prize_table.rank_bps.is_empty(),
+            ErrorCode::NoPrizeTable
+        );
+        
+        require!(
+            escrow.results_finalized_at.is_none(),
+            ErrorCode::ResultsAlreadyFinalized
+        );
+        
+        require!(
+            now < escrow.prize_table.results_deadline,
+            ErrorCode::ResultsDeadlinePassed
+        );
+        
+        require!(
+            if escrow.judges.is_empty() {
+                judge == escrow.sponsor
+            } else {
+                escrow.judges.contains(&judge)
+            },
+            ErrorCode::UnauthorizedJudge
+        );
+        
+        require!(
+            !winners.is_empty()
+                && winners.len() <= escrow.prize_table.rank_bps.len()
+                && winners.iter().enumerate().all(|(index, winner)| !winners[..index].contains(winner)),
+            ErrorCode::InvalidWinners
+        );
+
+        let approvals = escrow.submit_results(judge, &winners)?;
+
+        emit!(ResultsApproved {
+            escrow: escrow.key(),
+            judge,
+            winners: winners.clone(),
+            approvals,
+            judge_threshold: escrow.judge_threshold,
+        });
+
+        if approvals < escrow.judge_threshold {
+            return Ok(());
+        }
+
+        // Ranks without a winner leave their share for the sponsor to reclaim
+        let pool = escrow.prize_table.pool;
+        escrow.prizes = winners
+            .iter()
+            .zip(escrow.prize_table.rank_bps.iter())
+            .map(|(winner, bps)| {
+                Ok(Prize {
+                    winner: *winner,
+                    amount: bps_of(pool, *bps)?,
+                    claimed_at: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        escrow.results_finalized_at = Some(now);
+
+        emit!(ResultsFinalized {
+            escrow: escrow.key(),
+            prizes: escrow.prizes.clone(),
+            claim_deadline: escrow.claim_deadline().ok_or(ErrorCode::MathOverflow)?,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a winner's prize before the claim window closes
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let winner = ctx.accounts.winner.key();
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        let claim_deadline = escrow.claim_deadline().ok_or(ErrorCode::ResultsNotFinalized)?;
+        require!(
+            Clock::get()?.unix_timestamp < claim_deadline,
+            ErrorCode::ClaimWindowClosed
+        );
+        
+        let rank = escrow
+            .prizes
+            .iter()
+            .position(|prize| prize.winner == winner)
+            .ok_or(ErrorCode::NoPrizeForWinner)?;
+        
+        require!(
+            escrow.prizes[rank].claimed_at.is_none(),
+            ErrorCode::PrizeAlreadyClaimed
+        );
+
+        let amount = escrow.prizes[rank].amount;
+
+        let seeds = &[
+            b"sponsor_escrow",
+            escrow.quest_id.as_bytes(),
+            &[escrow.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: ctx.accounts.winner_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        escrow.prizes[rank].claimed_at = Some(Clock::get()?.unix_timestamp);
+        escrow.released_amount = escrow
+            .released_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Nothing is left to reclaim once every share of the pool is claimed
+        if escrow.unclaimed_prize_amount()? == 0 {
+            escrow.prizes_settled = true;
+        }
+        if escrow.is_settled() {
+            escrow.status = EscrowStatus::Completed;
+        }
+
+        emit!(PrizeClaimed {
+            escrow: escrow.key(),
+            winner,
+            rank: rank as u8 + 1,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return the unclaimed part of the prize pool to the contributors once
+    /// the claim window has closed, or the whole pool once the results
+    /// deadline has passed without results
+    pub fn reclaim_unclaimed_prizes<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimToContributors<'info>>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(
+            escrow.status == EscrowStatus::Active,
+            ErrorCode::EscrowNotActive
+        );
+        
+        if !escrow.results_overdue(now) {
+            let claim_deadline = escrow.claim_deadline().ok_or(ErrorCode::ResultsNotFinalized)?;
+            require!(
+                now >= claim_deadline,
+                ErrorCode::ClaimWindowOpen
+            );
+        }
+        
+        require!(
+            !escrow.prizes_settled,
+            ErrorCode::PrizesAlreadySettled
+        );
+
+        let amount = escrow.unclaimed_prize_amount()?;
+        let refunds = refund_contributors(
+            escrow,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+            amount,
+        )?;
+
+        escrow.refunded_amount = escrow
+            .refunded_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        escrow.prizes_settled = true;
+
+        if escrow.is_settled() {
+            escrow.status = EscrowStatus::Completed;
+        }
+
+        emit!(UnclaimedPrizesReclaimed {
+            escrow: escrow.key(),
+            amount,
+            refunds,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a refund held back because the contributor's associated token
+    /// account was missing or frozen. Anyone may call this, since it only ever
+    /// pays the contributor, into any token account they own.
+    pub fn pay_owed_refund(ctx: Context<PayOwedRefund>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let contributor_token_account = &ctx.accounts.contributor_token_account;
+        let contributor = contributor_token_account.owner;
+        
+        require!(
+            contributor_token_account.mint == escrow.mint,
+            ErrorCode::InvalidRecipientTokenAccount
+        );
+        
+        let index = escrow
+            .owed_refunds
+            .iter()
+            .position(|owed| owed.contributor == contributor)
+            .ok_or(ErrorCode::NoRefundOwed)?;
+        let amount = escrow.owed_refunds[index].amount;
+
+        let seeds = &[
+            b"sponsor_escrow",
+            escrow.quest_id.as_bytes(),
+            &[escrow.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: contributor_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        escrow.owed_refunds.remove(index);
+
+        emit!(OwedRefundPaid {
+            escrow: escrow.key(),
+            contributor,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return leftover tokens to the contributors and rent to the sponsor of a
+    /// settled escrow
+    pub fn close_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseEscrow<'info>>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        
+        require!(
+            escrow.status != EscrowStatus::Active,
+            ErrorCode::EscrowStillActive
+        );
+        
+        require!(
+            escrow.reserved_amount == 0,
+            ErrorCode::DistributionOutstanding
+        );
+        
+        require!(
+            escrow.owed_refunds.is_empty(),
+            ErrorCode::RefundsOutstanding
+        );
+
+        // Sweep rounding dust before closing the token account
+        let leftover = ctx.accounts.escrow_token_account.amount;
+        let refunds = refund_contributors(
+            escrow,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+            leftover,
+        )?;
+        
+        // Dust owed to an unpayable account would close with the token
+        // account; pay it with pay_owed_refund or pass another account
+        require!(
+            escrow.owed_refunds.is_empty(),
+            ErrorCode::RefundsOutstanding
+        );
+
+        let seeds = &[
+            b"sponsor_escrow",
+            escrow.quest_id.as_bytes(),
+            &[escrow.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow_token_account.to_account_info(),
+                destination: ctx.accounts.sponsor.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            signer,
+        ))?;
+
+        emit!(EscrowClosed {
+            escrow: escrow.key(),
+            sponsor: escrow.sponsor,
+            leftover,
+            refunds,
+        });
+
+        Ok(())
+    }
+
+    // =============================================================================
+    // TERRITORY NFT PROGRAM
+    // =============================================================================
+
+    /// Record the protocol authority allowed to create territories; only the
+    /// program's upgrade authority can set it up
+    pub fn initialize_protocol_config(
+        ctx: Context<InitializeProtocolConfig>,
+        authority: Pubkey,
+    ) -> Result<()> {
+        let protocol_config = &mut ctx.accounts.protocol_config;
+
+        protocol_config.authority = authority;
+        protocol_config.bump = ctx.bumps.protocol_config;
+
+        emit!(ProtocolConfigInitialized {
+            protocol_config: protocol_config.key(),
+            authority,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize a new territory NFT
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_territory(
+        ctx: Context<InitializeTerritory>,
+        territory_id: String,
+        name: String,
+        description: String,
+        coordinates: [i32; 2], // [x, y]
+        size: u32,
+        difficulty: u8,
+        max_teams: u16,
+        uri: String,
+    ) -> Result<()> {
+        let territory = &mut ctx.accounts.territory;
+        
+        require!(
+            (1..=5).contains(&difficulty),
+            ErrorCode::InvalidDifficulty
+        );
+        
+        require!(
+            !territory_id.is_empty() && territory_id.len() <= MAX_ID_LEN,
+            ErrorCode::InvalidTerritoryId
+        );
+        
+        require!(
+            name.len() <= MAX_TITLE_LEN,
+            ErrorCode::TitleTooLong
+        );
+        
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            ErrorCode::DescriptionTooLong
+        );
+        
+        require!(
+            uri.len() <= MAX_URI_LEN,
+            ErrorCode::UriTooLong
+        );
+
+        territory.territory_id = territory_id;
+        territory.name = name;
+        territory.description = description;
+        territory.coordinates = coordinates;
+        territory.size = size;
+        territory.difficulty = difficulty;
+        territory.max_teams = max_teams;
+        territory.current_teams = 0;
+        territory.uri = uri;
+        territory.owner = None;
+        territory.battles_won = 0;
+        territory.battles_lost = 0;
+        territory.total_rewards = 0;
+        territory.is_active = true;
+        territory.authority = ctx.accounts.authority.key();
+        territory.bump = ctx.bumps.territory;
+        territory.created_at = Clock::get()?.unix_timestamp;
+
+        emit!(TerritoryCreated {
+            territory: territory.key(),
+            territory_id: territory.territory_id.clone(),
+            name: territory.name.clone(),
+            coordinates,
+            difficulty,
+        });
+
+        Ok(())
+    }
+
+    /// Initiate a battle for territory control
+    pub fn challenge_territory(
+        ctx: Context<ChallengeTerritory>,
+        challenger_team_id: String,
+        battle_type: BattleType,
+    ) -> Result<()> {
+        let territory = &ctx.accounts.territory;
+        let battle = &mut ctx.accounts.battle;
+        
+        require!(
+            territory.is_active,
+            ErrorCode::TerritoryNotActive
+        );
+        
+        require!(
+            !challenger_team_id.is_empty() && challenger_team_id.len() <= MAX_ID_LEN,
+            ErrorCode::InvalidTeamId
+        );
+        
+        // Create battle record
+        battle.territory = ctx.accounts.territory.key();
+        battle.challenger = ctx.accounts.challenger.key();
+        battle.challenger_team_id = challenger_team_id;
+        battle.defender = territory.owner;
+        battle.battle_type = battle_type.clone();
+        battle.status = BattleStatus::Pending;
+        battle.stake_amount = 0; // To be set based on battle type
+        battle.created_at = Clock::get()?.unix_timestamp;
+        battle.expires_at = Clock::get()?.unix_timestamp + 24 * 60 * 60; // 24 hours
+
+        emit!(TerritoryChallenge {
+            territory: territory.key(),
+            battle: battle.key(),
+            challenger: ctx.accounts.challenger.key(),
+            battle_type,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a territory battle; no winner records a failed challenge, which
+    /// is how a challenge for an unowned territory is lost
+    pub fn resolve_battle(
+        ctx: Context<ResolveBattle>,
+        winner: Option<Pubkey>,
+        score: u32,
+    ) -> Result<()> {
+        let territory = &mut ctx.accounts.territory;
+        let battle = &mut ctx.accounts.battle;
+        
+        require!(
+            battle.status == BattleStatus::Pending,
+            ErrorCode::BattleNotActive
+        );
+        
+        require!(
+            winner.is_none() || winner == Some(battle.challenger) || winner == battle.defender,
+            ErrorCode::InvalidBattleWinner
+        );
+
+        battle.status = BattleStatus::Completed;
+        battle.winner = winner;
+        battle.score = score;
+        battle.resolved_at = Some(Clock::get()?.unix_timestamp);
+
+        // Update territory ownership if challenger wins
+        if winner == Some(battle.challenger) {
+            territory.owner = Some(battle.challenger);
+            territory.battles_won += 1;
+        } else {
+            territory.battles_lost += 1;
+        }
+
+        emit!(BattleResolved {
+            territory: territory.key(),
+            battle: battle.key(),
+            winner,
+            score,
+        });
+
+        Ok(())
+    }
+}
+
+// =============================================================================
+// ACCOUNT STRUCTURES
+// =============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct TeamVault {
+    #[max_len(MAX_ID_LEN)]
+    pub team_id: String,
+    #[max_len(MAX_TEAM_NAME_LEN)]
+    pub name: String,
+    #[max_len(MAX_FOUNDERS)]
+    pub founders: Vec<Pubkey>,
+    #[max_len(MAX_FOUNDERS)]
+    pub founder_weights: Option<Vec<u16>>, // Equity in basis points, parallel to `founders`
+    pub threshold: u8, // Founder count, or percentage of total weight when weighted
+    pub governance: GovernanceConfig,
+    #[max_len(MAX_ALLOWED_PROGRAMS)]
+    pub allowed_programs: Vec<Pubkey>, // Programs the vault may invoke via proposal
+    #[max_len(MAX_ALLOWANCES)]
+    pub allowances: Vec<SpendingAllowance>,
+    #[max_len(MAX_FOUNDERS)]
+    pub delegations: Vec<VoteDelegation>, // At most one per founder
+    #[max_len(MAX_FOUNDERS)]
+    pub vesting_founders: Vec<Pubkey>, // Founders with a live FounderVesting account
+    pub arbitrator: Option<Pubkey>, // Resolves disputes the founders cannot
+    pub dispute_count: u64,
+    pub open_disputes: u16, // Disputes still awaiting resolution
+    #[max_len(MAX_FOUNDERS)]
+    pub freezes: Vec<VaultFreeze>, // Freezes from disputes, kept through their cooldown, one per founder
+    pub agreement_version: u32, // Adopted FounderAgreement version, 0 if none
+    pub agreement_required: bool, // Governance changes need an agreement signed by the current founders
+    pub agreement_current: bool, // The adopted agreement lists exactly the current founders
+    #[max_len(MAX_VAULT_MINTS)]
+    pub balances: Vec<VaultBalance>, // Tracked funds per mint, without empty entries
+    pub proposal_count: u64,
+    pub config_version: u32, // Bumped on every executed governance change
+    pub bump: u8,
+    pub created_at: i64,
+    pub is_active: bool,
+    pub closed: bool, // Settled after separation; kept as a tombstone for its team id
+}
+
+impl TeamVault {
+    pub fn founder_index(&self, founder: &Pubkey) -> Option<usize> {
+        self.founders.iter().position(|f| f == founder)
+    }
+
+    /// Voting weight of a founder: 1 per founder, or their equity in basis points
+    pub fn vote_weight(&self, founder: &Pubkey) -> u64 {
+        match (self.founder_index(founder), &self.founder_weights) {
+            (None, _) => 0,
+            (Some(_), None) => 1,
+            (Some(index), Some(weights)) => weights[index] as u64,
+        }
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        match &self.founder_weights {
+            None => self.founders.len() as u64,
+            Some(weights) => weights.iter().map(|w| *w as u64).sum(),
+        }
+    }
+
+    /// Weight needed to approve a proposal, rounding percentages up
+    pub fn required_weight(&self) -> u64 {
+        match &self.founder_weights {
+            None => self.threshold as u64,
+            Some(_) => (self.total_weight() * self.threshold as u64).div_ceil(100),
+        }
+    }
+
+    /// Total weight of the founders other than `lost_founder`, and the weight
+    /// they need to approve its recovery. A head-count threshold scales down
+    /// with the founder count so a lost key cannot make it unreachable.
+    pub fn recovery_weights(&self, lost_founder: &Pubkey) -> (u64, u64) {
+        let total_weight = self.total_weight() - self.vote_weight(lost_founder);
+        let required_weight = match &self.founder_weights {
+            None => (self.threshold as u64 * total_weight).div_ceil(self.founders.len() as u64),
+            Some(_) => (total_weight * self.threshold as u64).div_ceil(100),
+        };
+        (total_weight, required_weight)
+    }
+
+    /// Timelock between approval and execution; recoveries always wait at
+    /// least FOUNDER_RECOVERY_DELAY
+    pub fn execution_delay(&self, proposal_type: &ProposalType) -> i64 {
+        match proposal_type {
+            ProposalType::RecoverFounder { .. } => self.governance.execution_delay.max(FOUNDER_RECOVERY_DELAY),
+            _ => self.governance.execution_delay,
+        }
+    }
+
+    /// Freeze the vault for `initiator` until `until`. Each founder holds one
+    /// freeze at a time and must wait a further voting period once it ends
+    /// before freezing again, so the other founders always get a window to
+    /// execute proposals, including one removing that founder.
+    pub fn freeze(&mut self, initiator: Pubkey, until: i64, now: i64) -> Result<()> {
+        let cooldown = self.governance.voting_period;
+        self.freezes
+            .retain(|freeze| now < freeze.until.saturating_add(cooldown));
+        if let Some(freeze) = self.freezes.iter().find(|freeze| freeze.initiator == initiator) {
+            require!(
+                now >= freeze.until,
+                ErrorCode::FreezeAlreadyOpen
+            );
+            return err!(ErrorCode::FreezeCoolingDown);
+        }
+
+        self.freezes.push(VaultFreeze {
+            initiator,
+            until,
+        });
+
+        Ok(())
+    }
+
+    /// Whether an open dispute still blocks execute_proposal at `now`
+    pub fn is_frozen(&self, now: i64) -> bool {
+        self.freezes.iter().any(|freeze| now < freeze.until)
+    }
+
+    /// Tracked balance of `mint`
+    pub fn balance_of(&self, mint: &Pubkey) -> u64 {
+        self.balances
+            .iter()
+            .find(|balance| balance.mint == *mint)
+            .map_or(0, |balance| balance.amount)
+    }
+
+    /// Add `amount` to the balance of `mint` and return the new balance
+    pub fn credit(&mut self, mint: Pubkey, amount: u64) -> Result<u64> {
+        if amount == 0 {
+            return Ok(self.balance_of(&mint));
+        }
+
+        match self.balances.iter_mut().find(|balance| balance.mint == mint) {
+            Some(balance) => {
+                balance.amount = balance.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+                Ok(balance.amount)
+            },
+            None => {
+                require!(
+                    self.balances.len() < MAX_VAULT_MINTS,
+                    ErrorCode::TooManyVaultMints
+                );
+                self.balances.push(VaultBalance { mint, amount });
+                Ok(amount)
+            },
+        }
+    }
+
+    /// Take `amount` off the balance of `mint` and return the new balance,
+    /// dropping the entry once it is empty
+    pub fn debit(&mut self, mint: Pubkey, amount: u64) -> Result<u64> {
+        if amount == 0 {
+            return Ok(self.balance_of(&mint));
+        }
+
+        let index = self
+            .balances
+            .iter()
+            .position(|balance| balance.mint == mint)
+            .ok_or(ErrorCode::InsufficientVaultFunds)?;
+
+        let remaining = self.balances[index]
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientVaultFunds)?;
+        if remaining == 0 {
+            self.balances.remove(index);
+        } else {
+            self.balances[index].amount = remaining;
+        }
+
+        Ok(remaining)
+    }
+
+    /// Debit tokens that left a vault token account, up to the tracked balance;
+    /// tokens sent straight to the vault were never tracked
+    pub fn debit_tracked(&mut self, mint: Pubkey, amount: u64) -> Result<u64> {
+        self.debit(mint, amount.min(self.balance_of(&mint)))
+    }
+
+    /// Close the vault, keeping only what its tombstone needs: the team id
+    /// and bump that pin its address
+    pub fn retire(&mut self) {
+        self.closed = true;
+        self.name.clear();
+        self.founders.clear();
+        self.founder_weights = None;
+        self.allowed_programs.clear();
+        self.allowances.clear();
+        self.delegations.clear();
+        self.arbitrator = None;
+        self.freezes.clear();
+    }
+
+    /// Check that `new_founder` can take over `old_founder`'s seat
+    pub fn validate_founder_replacement(&self, old_founder: &Pubkey, new_founder: &Pubkey) -> Result<()> {
+        require!(
+            self.founders.contains(old_founder),
+            ErrorCode::FounderNotFound
+        );
+        require!(
+            !self.founders.contains(new_founder),
+            ErrorCode::FounderAlreadyExists
+        );
+        require!(
+            self.arbitrator != Some(*new_founder),
+            ErrorCode::InvalidArbitrator
+        );
+
+        Ok(())
+    }
+
+    /// Move a founder's seat, weight, allowances, delegation and vesting entry
+    /// to a new key. Delegations held by the old key are dropped.
+    pub fn replace_founder(&mut self, old_founder: &Pubkey, new_founder: Pubkey) -> Result<()> {
+        self.validate_founder_replacement(old_founder, &new_founder)?;
+
+        let index = self.founder_index(old_founder).ok_or(ErrorCode::FounderNotFound)?;
+        self.founders[index] = new_founder;
+        self.agreement_current = false;
+
+        for allowance in self.allowances.iter_mut() {
+            if allowance.founder == *old_founder {
+                allowance.founder = new_founder;
+            }
+        }
+        for vesting_founder in self.vesting_founders.iter_mut() {
+            if vesting_founder == old_founder {
+                *vesting_founder = new_founder;
+            }
+        }
+        for delegation in self.delegations.iter_mut() {
+            if delegation.founder == *old_founder {
+                delegation.founder = new_founder;
+            }
+        }
+        for freeze in self.freezes.iter_mut() {
+            if freeze.initiator == *old_founder {
+                freeze.initiator = new_founder;
+            }
+        }
+        self.delegations
+            .retain(|delegation| delegation.delegate != *old_founder && delegation.delegate != delegation.founder);
+
+        Ok(())
+    }
+
+    /// Check that a governance proposal would leave the vault in a valid state
+    pub fn validate_proposal_type(&self, proposal_type: &ProposalType) -> Result<()> {
+        // Once a vault binds itself to a founder agreement, a change to the
+        // founder set must be followed by a newly signed agreement before any
+        // further governance change
+        require!(
+            !proposal_type.needs_current_agreement() || !self.agreement_required || self.agreement_current,
+            ErrorCode::AgreementOutOfDate
+        );
+
+        match proposal_type {
+            ProposalType::Transfer => {},
+            ProposalType::BatchTransfer { payouts } => {
+                require!(
+                    !payouts.is_empty() && payouts.len() <= MAX_PAYOUTS,
+                    ErrorCode::InvalidPayouts
+                );
+                require!(
+                    payouts.iter().all(|payout| payout.amount > 0),
+                    ErrorCode::InvalidPayouts
+                );
+            },
+            ProposalType::AddFounder { founder, weight_bps } => {
+                require!(
+                    !self.founders.contains(founder),
+                    ErrorCode::FounderAlreadyExists
+                );
+                require!(
+                    self.founders.len() < MAX_FOUNDERS,
+                    ErrorCode::TooManyFounders
+                );
+                require!(
+                    self.arbitrator != Some(*founder),
+                    ErrorCode::InvalidArbitrator
+                );
+                let founder_weights = self.founder_weights.clone().map(|mut weights| {
+                    weights.push(*weight_bps);
+                    weights
+                });
+                require!(
+                    founder_weights.is_some() || *weight_bps == 0,
+                    ErrorCode::InvalidFounderWeights
+                );
+                validate_voting_rules(self.founders.len() + 1, &founder_weights, self.threshold)?;
+            },
+            ProposalType::RemoveFounder { founder } => {
+                let index = self.founder_index(founder).ok_or(ErrorCode::FounderNotFound)?;
+                let founder_weights = self.founder_weights.clone().map(|mut weights| {
+                    weights.remove(index);
+                    weights
+                });
+                validate_voting_rules(self.founders.len() - 1, &founder_weights, self.threshold)?;
+                self.governance.validate(self.founders.len() - 1)?;
+            },
+            ProposalType::ChangeThreshold { threshold } => {
+                validate_voting_rules(self.founders.len(), &self.founder_weights, *threshold)?;
+            },
+            ProposalType::SetFounderWeights { founder_weights, threshold } => {
+                validate_voting_rules(self.founders.len(), founder_weights, *threshold)?;
+            },
+            ProposalType::RenameTeam { name } => {
+                require!(
+                    !name.is_empty() && name.len() <= MAX_TEAM_NAME_LEN,
+                    ErrorCode::InvalidTeamName
+                );
+            },
+            ProposalType::UpdateGovernance { config } => {
+                config.validate(self.founders.len())?;
+            },
+            ProposalType::ExecuteInstructions { instructions } => {
+                require!(
+                    !instructions.is_empty() && instructions.len() <= MAX_VAULT_INSTRUCTIONS,
+                    ErrorCode::InvalidVaultInstructions
+                );
+                for vault_instruction in instructions {
+                    require!(
+                        vault_instruction.accounts.len() <= MAX_INSTRUCTION_ACCOUNTS
+                            && vault_instruction.data.len() <= MAX_INSTRUCTION_DATA_LEN,
+                        ErrorCode::InvalidVaultInstructions
+                    );
+                    require!(
+                        self.allowed_programs.contains(&vault_instruction.program_id),
+                        ErrorCode::ProgramNotAllowed
+                    );
+                }
+            },
+            ProposalType::CreatePaymentStream { rate_per_second, start_time, cliff_time, end_time, .. } => {
+                require!(
+                    *rate_per_second > 0
+                        && start_time < end_time
+                        && start_time <= cliff_time
+                        && cliff_time <= end_time,
+                    ErrorCode::InvalidPaymentStream
+                );
+                let duration = end_time.checked_sub(*start_time).ok_or(ErrorCode::MathOverflow)?;
+                require!(
+                    duration > 0,
+                    ErrorCode::InvalidPaymentStream
+                );
+                (*rate_per_second as u128)
+                    .checked_mul(duration as u128)
+                    .filter(|total| *total <= u64::MAX as u128)
+                    .ok_or(ErrorCode::InvalidPaymentStream)?;
+            },
+            ProposalType::CancelPaymentStream { .. } => {},
+            ProposalType::SeparateTeam { distribution } => {
+                require!(
+                    !distribution.is_empty() && distribution.len() <= MAX_FOUNDERS,
+                    ErrorCode::InvalidDistribution
+                );
+                require!(
+                    distribution.iter().map(|share| share.share_bps as u32).sum::<u32>() == 10_000,
+                    ErrorCode::InvalidDistribution
+                );
+                for (index, share) in distribution.iter().enumerate() {
+                    require!(
+                        self.founders.contains(&share.founder),
+                        ErrorCode::FounderNotFound
+                    );
+                    require!(
+                        !distribution[..index].iter().any(|other| other.founder == share.founder),
+                        ErrorCode::InvalidDistribution
+                    );
+                }
+            },
+            ProposalType::CreateFounderVesting {
+                founder,
+                total_amount,
+                cliff_duration,
+                vesting_duration,
+                ..
+            } => {
+                require!(
+                    self.founders.contains(founder),
+                    ErrorCode::FounderNotFound
+                );
+                require!(
+                    !self.vesting_founders.contains(founder),
+                    ErrorCode::FounderVestingExists
+                );
+                require!(
+                    *total_amount > 0
+                        && *vesting_duration > 0
+                        && *cliff_duration >= 0
+                        && cliff_duration <= vesting_duration,
+                    ErrorCode::InvalidVestingSchedule
+                );
+            },
+            ProposalType::SetAllowance { founder, mint, amount_per_period, period } => {
+                require!(
+                    self.founders.contains(founder),
+                    ErrorCode::FounderNotFound
+                );
+                require!(
+                    *amount_per_period == 0 || *period > 0,
+                    ErrorCode::InvalidAllowance
+                );
+                let replaces_existing = self
+                    .allowances
+                    .iter()
+                    .any(|allowance| allowance.founder == *founder && allowance.mint == *mint);
+                require!(
+                    replaces_existing || self.allowances.len() < MAX_ALLOWANCES,
+                    ErrorCode::TooManyAllowances
+                );
+            },
+            ProposalType::SetArbitrator { arbitrator } => {
+                // The fallback for founder deadlocks cannot be one of the founders
+                if let Some(arbitrator) = arbitrator {
+                    require!(
+                        !self.founders.contains(arbitrator),
+                        ErrorCode::InvalidArbitrator
+                    );
+                }
+            },
+            ProposalType::RecoverFounder { lost_founder, new_founder } => {
+                require!(
+                    self.founders.len() > 1,
+                    ErrorCode::InvalidFounderRecovery
+                );
+                self.validate_founder_replacement(lost_founder, new_founder)?;
+            },
+            ProposalType::SetAllowedPrograms { programs } => {
+                require!(
+                    programs.len() <= MAX_ALLOWED_PROGRAMS,
+                    ErrorCode::TooManyAllowedPrograms
+                );
+                // The vault must not be able to re-enter its own program
+                require!(
+                    !programs.contains(&crate::ID),
+                    ErrorCode::ProgramNotAllowed
+                );
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Check founder weights and threshold against each other. Without weights the
+/// threshold is a founder count; with weights it is a percentage of total weight.
+pub fn validate_voting_rules(
+    founder_count: usize,
+    founder_weights: &Option<Vec<u16>>,
+    threshold: u8,
+) -> Result<()> {
+    match founder_weights {
+        None => {
+            require!(
+                threshold > 0 && threshold as usize <= founder_count,
+                ErrorCode::InvalidThreshold
+            );
+        },
+        Some(weights) => {
+            require!(
+                weights.len() == founder_count,
+                ErrorCode::InvalidFounderWeights
+            );
+            require!(
+                weights.iter().all(|w| *w > 0)
+                    && weights.iter().map(|w| *w as u32).sum::<u32>() <= 10_000,
+                ErrorCode::InvalidFounderWeights
+            );
+            require!(
+                threshold > 0 && threshold <= 100,
+                ErrorCode::InvalidThreshold
+            );
+        },
+    }
+
+    Ok(())
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub team_vault: Pubkey,
+    pub proposer: Pubkey,
+    #[max_len(MAX_TITLE_LEN)]
+    pub title: String,
+    #[max_len(MAX_DESCRIPTION_LEN)]
+    pub description: String,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub mint: Option<Pubkey>, // Mint a transfer pays out in
+    pub proposal_type: ProposalType,
+    #[max_len(MAX_FOUNDERS)]
+    pub votes: Vec<Vote>,
+    pub status: ProposalStatus,
+    pub config_version: u32,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub approved_at: Option<i64>,
+    pub executed_at: Option<i64>,
+    pub executed_payouts: u8, // Batch payouts already paid or skipped, in order
+    pub skipped_payouts: u16, // Bitmap of batch payouts skipped as unpayable
+    pub index: u64, // Position in the vault's proposal sequence, used in the PDA seeds
+    pub bump: u8,
+}
+
+impl Proposal {
+    /// Whether the batch payout at `index` was skipped and is still owed
+    pub fn is_payout_skipped(&self, index: usize) -> bool {
+        index < u16::BITS as usize && self.skipped_payouts & (1 << index) != 0
+    }
+
+    /// Record a founder's vote, cast by the founder or their delegate, after
+    /// checking the proposal is still open to it
+    pub fn cast_vote(
+        &mut self,
+        team_vault: &TeamVault,
+        voter: Pubkey,
+        delegator: Option<Pubkey>,
+        support: bool,
+        now: i64,
+    ) -> Result<()> {
+        let founder = delegator.unwrap_or(voter);
+
+        // A delegate needs a live delegation from the founder it votes for
+        if let Some(delegator) = delegator {
+            require!(
+                team_vault.delegations.iter().any(|delegation| {
+                    delegation.founder == delegator
+                        && delegation.delegate == voter
+                        && now < delegation.expires_at
+                }),
+                ErrorCode::InvalidDelegation
+            );
+        }
+
+        // Verify the vote belongs to a founder
+        require!(
+            team_vault.founders.contains(&founder),
+            ErrorCode::UnauthorizedFounder
+        );
+        
+        // Check if the founder already voted, directly or through a delegate
+        require!(
+            !self.votes.iter().any(|vote| vote.founder() == founder),
+            ErrorCode::AlreadyVoted
+        );
+        
+        // Check if proposal is still active
+        require!(
+            self.status == ProposalStatus::Pending,
+            ErrorCode::ProposalNotActive
+        );
+        
+        // Votes only count against the founder set the proposal was opened under
+        require!(
+            self.config_version == team_vault.config_version,
+            ErrorCode::StaleProposal
+        );
+        
+        require!(
+            now < self.expires_at,
+            ErrorCode::ProposalExpired
+        );
+
+        self.votes.push(Vote {
+            voter,
+            delegator,
+            support,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Approve or reject the proposal once the recorded votes decide it
+    pub fn tally_votes(&mut self, proposal: Pubkey, team_vault: &Account<TeamVault>, now: i64) -> Result<()> {
+        // A recovery is decided by the remaining founders alone; the key being
+        // replaced can only veto it by voting against, proving it is reachable
+        let lost_founder = self.proposal_type.lost_founder();
+        let vetoed = self
+            .votes
+            .iter()
+            .any(|vote| Some(vote.founder()) == lost_founder && !vote.support);
+        let counted_votes = || self.votes.iter().filter(|vote| Some(vote.founder()) != lost_founder);
+        let (total_weight, required_weight, quorum) = match lost_founder {
+            Some(lost_founder) => {
+                let (total_weight, required_weight) = team_vault.recovery_weights(&lost_founder);
+                let remaining_founders = team_vault.founders.len().saturating_sub(1) as u8;
+                let quorum = team_vault.governance.quorum.unwrap_or(0).min(remaining_founders);
+                (total_weight, required_weight, quorum)
+            },
+            None => (
+                team_vault.total_weight(),
+                team_vault.required_weight(),
+                team_vault.governance.quorum.unwrap_or(0),
+            ),
+        };
+
+        // Check if proposal has enough voting weight to execute
+        let support_votes = counted_votes().filter(|v| v.support).count();
+        let against_votes = counted_votes().count() - support_votes;
+        let (support_weight, against_weight) = counted_votes().fold(
+            (0u64, 0u64),
+            |(support_weight, against_weight), vote| {
+                let weight = team_vault.vote_weight(&vote.founder());
+                if vote.support {
+                    (support_weight + weight, against_weight)
+                } else {
+                    (support_weight, against_weight + weight)
+                }
+            },
+        );
+        let quorum_reached = counted_votes().count() >= quorum as usize;
+        if !vetoed && support_weight >= required_weight && quorum_reached {
+            self.status = ProposalStatus::Approved;
+            self.approved_at = Some(now);
+            // Once the timelock elapses the proposal gets another voting period
+            // to be executed in
+            self.expires_at = now
+                .checked_add(team_vault.execution_delay(&self.proposal_type))
+                .and_then(|executable_at| executable_at.checked_add(team_vault.governance.voting_period))
+                .ok_or(ErrorCode::MathOverflow)?;
+            
+            emit!(ProposalApproved {
+                proposal,
+                team_vault: team_vault.key(),
+                votes: support_votes as u8,
+                support_weight,
+                required_weight,
+            });
+        } else if vetoed || total_weight.saturating_sub(against_weight) < required_weight {
+            // Not enough voting weight left to reach the threshold
+            self.status = ProposalStatus::Rejected;
+            
+            emit!(ProposalRejected {
+                proposal,
+                team_vault: team_vault.key(),
+                votes_against: against_votes as u8,
+                against_weight,
+                required_weight,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Mint and amount of each distinct token account in `accounts` controlled by
+/// `authority`
+pub fn vault_token_holdings(accounts: &[AccountInfo], authority: &Pubkey) -> Vec<(Pubkey, u64)> {
+    accounts
+        .iter()
+        .enumerate()
+        .filter(|(index, account)| {
+            *account.owner == token::ID && !accounts[..*index].iter().any(|other| other.key == account.key)
+        })
+        .filter_map(|(_, account)| {
+            let data = account.try_borrow_data().ok()?;
+            TokenAccount::try_deserialize(&mut &data[..]).ok()
+        })
+        .filter(|token_account| token_account.owner == *authority)
+        .map(|token_account| (token_account.mint, token_account.amount))
+        .collect()
+}
+
+/// Whether `account` is `recipient`'s associated token account for `mint` and
+/// cannot take a transfer because it is not open or is frozen. Any other
+/// account is left to the usual token account checks.
+pub fn is_unpayable_recipient(account: &AccountInfo, recipient: &Pubkey, mint: &Pubkey) -> bool {
+    if account.key() != get_associated_token_address(recipient, mint) {
+        return false;
+    }
+    if *account.owner != token::ID {
+        return true;
+    }
+
+    account
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| TokenAccount::try_deserialize(&mut &data[..]).ok())
+        .map(|token_account| token_account.is_frozen())
+        .unwrap_or(true)
+}
+
+/// Start of every signed vote on `proposal`
+pub fn signed_vote_prefix(team_vault: &Pubkey, proposal: &Pubkey) -> Vec<u8> {
+    let mut prefix = b"rise_of_founders:vote".to_vec();
+    prefix.extend_from_slice(team_vault.as_ref());
+    prefix.extend_from_slice(proposal.as_ref());
+    prefix
+}
+
+/// Canonical message a founder signs off-chain to vote on a proposal
+pub fn signed_vote_message(team_vault: &Pubkey, proposal: &Pubkey, support: bool, nonce: u64) -> Vec<u8> {
+    let mut message = signed_vote_prefix(team_vault, proposal);
+    message.push(support as u8);
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+/// The vote a signed message casts on `proposal`, or None when the message is
+/// not about it. A message naming the proposal must be a canonical vote; the
+/// nonce is the proposal index, binding the signature to its slot in the
+/// vault's history.
+pub fn signed_vote_support(message: &[u8], team_vault: &Pubkey, proposal: &Pubkey, nonce: u64) -> Result<Option<bool>> {
+    if !message.starts_with(&signed_vote_prefix(team_vault, proposal)) {
+        return Ok(None);
+    }
+
+    if message == signed_vote_message(team_vault, proposal, true, nonce) {
+        Ok(Some(true))
+    } else if message == signed_vote_message(team_vault, proposal, false, nonce) {
+        Ok(Some(false))
+    } else {
+        err!(ErrorCode::InvalidSignedVote)
+    }
+}
+
+/// Extract (public key, message) pairs from an ed25519 precompile instruction.
+/// Only signatures whose key, signature and message all live inside that same
+/// instruction are returned; the rest point at data this program cannot tie
+/// to a vote and are skipped.
+pub fn ed25519_signed_messages(data: &[u8]) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+
+    let count = *data.first().ok_or(ErrorCode::InvalidSignedVote)? as usize;
+    let mut signed_messages = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let start = HEADER_LEN + i * OFFSETS_LEN;
+        let offsets = data
+            .get(start..start + OFFSETS_LEN)
+            .ok_or(ErrorCode::InvalidSignedVote)?;
+        let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+        let signature_instruction = read(2);
+        let public_key_offset = read(4) as usize;
+        let public_key_instruction = read(6);
+        let message_offset = read(8) as usize;
+        let message_size = read(10) as usize;
+        let message_instruction = read(12);
+
+        if signature_instruction != u16::MAX
+            || public_key_instruction != u16::MAX
+            || message_instruction != u16::MAX
+        {
+            continue;
+        }
+
+        let public_key = data
+            .get(public_key_offset..public_key_offset + 32)
+            .ok_or(ErrorCode::InvalidSignedVote)?;
+        let message = data
+            .get(message_offset..message_offset + message_size)
+            .ok_or(ErrorCode::InvalidSignedVote)?;
+
+        signed_messages.push((
+            Pubkey::try_from(public_key).map_err(|_| error!(ErrorCode::InvalidSignedVote))?,
+            message.to_vec(),
+        ));
+    }
+
+    Ok(signed_messages)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FounderVesting {
+    pub team_vault: Pubkey,
+    pub founder: Pubkey, // Key the PDA was derived from
+    pub beneficiary: Pubkey, // Founder's current key, which follows key rotations
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub claimed: u64,
+    pub start_time: i64,
+    pub cliff_duration: i64,
+    pub vesting_duration: i64,
+    pub revocable: bool,
+    pub revoked_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl FounderVesting {
+    /// Amount vested at `now`, linear after the cliff and frozen once revoked
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        let vested_until = self.revoked_at.map_or(now, |revoked_at| revoked_at.min(now));
+        let elapsed = vested_until.saturating_sub(self.start_time);
+        if elapsed < self.cliff_duration {
+            return Ok(0);
+        }
+        if elapsed >= self.vesting_duration {
+            return Ok(self.total_amount);
+        }
+
+        let vested = (self.total_amount as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / self.vesting_duration as u128;
+
+        Ok(vested as u64)
+    }
+
+    /// Stop vesting at `now` and return the unvested amount to send back to the vault
+    pub fn revoke(&mut self, now: i64) -> Result<u64> {
+        require!(
+            self.revocable && self.revoked_at.is_none(),
+            ErrorCode::VestingNotRevocable
+        );
+
+        self.revoked_at = Some(now);
+        let vested = self.vested_amount(now)?;
+
+        Ok(self.total_amount.checked_sub(vested).ok_or(ErrorCode::MathOverflow)?)
+    }
+}
+
+/// Take a founder's vesting off the vault, returning the unvested tokens to the
+/// vault token account when the schedule is revocable
+/// Pay a vault token account out to the founders by their separation shares
+/// and close it, returning the amount distributed. The founders' token
+/// accounts are `recipient_accounts`, in distribution order.
+pub fn distribute_vault_token_account<'info>(
+    team_vault: &mut Account<'info, TeamVault>,
+    vault_token_account: &mut Account<'info, TokenAccount>,
+    distribution: &[SeparationShare],
+    recipient_accounts: &'info [AccountInfo<'info>],
+    rent_destination: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<u64> {
+    vault_token_account.reload()?;
+    let balance = vault_token_account.amount;
+    require!(
+        recipient_accounts.len() == distribution.len(),
+        ErrorCode::MissingTokenAccount
+    );
+
+    let team_id = team_vault.team_id.clone();
+    let seeds = &[
+        b"team_vault",
+        team_id.as_bytes(),
+        &[team_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let mut distributed = 0u64;
+    for (index, (share, recipient_info)) in distribution
+        .iter()
+        .zip(recipient_accounts.iter())
+        .enumerate()
+    {
+        let recipient_token_account = Account::<TokenAccount>::try_from(recipient_info)?;
+        require!(
+            recipient_token_account.owner == share.founder
+                && recipient_token_account.mint == vault_token_account.mint,
+            ErrorCode::InvalidRecipientTokenAccount
+        );
+
+        // The last share takes the rounding dust so the account drains fully
+        let share_amount = if index == distribution.len() - 1 {
+            balance.checked_sub(distributed).ok_or(ErrorCode::MathOverflow)?
+        } else {
+            bps_of(balance, share.share_bps)?
+        };
+        distributed = distributed.checked_add(share_amount).ok_or(ErrorCode::MathOverflow)?;
+
+        if share_amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: vault_token_account.to_account_info(),
+                        to: recipient_token_account.to_account_info(),
+                        authority: team_vault.to_account_info(),
+                    },
+                    signer,
+                ),
+                share_amount,
+            )?;
+        }
+    }
+
+    // Tokens sent straight to the vault were never tracked
+    team_vault.debit_tracked(vault_token_account.mint, balance)?;
+
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault_token_account.to_account_info(),
+            destination: rent_destination,
+            authority: team_vault.to_account_info(),
+        },
+        signer,
+    ))?;
+
+    Ok(balance)
+}
+
+pub fn settle_founder_vesting<'info>(
+    team_vault: &mut Account<'info, TeamVault>,
+    founder_vesting: &mut Account<'info, FounderVesting>,
+    vault_token_account: Option<&Account<'info, TokenAccount>>,
+    vesting_token_account: Option<&Account<'info, TokenAccount>>,
+    rent_destination: Option<AccountInfo<'info>>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let founder = founder_vesting.founder;
+    let position = team_vault
+        .vesting_founders
+        .iter()
+        .position(|f| *f == founder_vesting.beneficiary)
+        .ok_or(ErrorCode::MissingFounderVesting)?;
+    require!(
+        founder_vesting.team_vault == team_vault.key(),
+        ErrorCode::MissingFounderVesting
+    );
+
+    if founder_vesting.revocable {
+        let vault_token_account = vault_token_account.ok_or(ErrorCode::MissingTokenAccount)?;
+        let vesting_token_account = vesting_token_account.ok_or(ErrorCode::MissingTokenAccount)?;
+        require!(
+            vesting_token_account.owner == founder_vesting.key()
+                && vault_token_account.mint == founder_vesting.mint,
+            ErrorCode::InvalidVestingTokenAccount
+        );
+
+        let returned = founder_vesting.revoke(Clock::get()?.unix_timestamp)?;
+
+        let team_vault_key = team_vault.key();
+        let seeds = &[
+            b"founder_vesting",
+            team_vault_key.as_ref(),
+            founder.as_ref(),
+            &[founder_vesting.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: vesting_token_account.to_account_info(),
+                    to: vault_token_account.to_account_info(),
+                    authority: founder_vesting.to_account_info(),
+                },
+                signer,
+            ),
+            returned,
+        )?;
+
+        // With everything vested already claimed the founder is owed nothing,
+        // so stray tokens join the returned ones and the vesting closes
+        let owed = founder_vesting
+            .vested_amount(i64::MAX)?
+            .checked_sub(founder_vesting.claimed)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let stray = if owed == 0 {
+            vesting_token_account.amount.saturating_sub(returned)
+        } else {
+            0
+        };
+        if stray > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: vesting_token_account.to_account_info(),
+                        to: vault_token_account.to_account_info(),
+                        authority: founder_vesting.to_account_info(),
+                    },
+                    signer,
+                ),
+                stray,
+            )?;
+        }
+
+        let balance = team_vault.credit(founder_vesting.mint, returned + stray)?;
+
+        emit!(FounderVestingRevoked {
+            founder_vesting: founder_vesting.key(),
+            team_vault: team_vault_key,
+            founder,
+            mint: founder_vesting.mint,
+            returned_amount: returned + stray,
+            balance,
+        });
+
+        if owed == 0 {
+            close_founder_vesting(
+                founder_vesting,
+                vesting_token_account,
+                rent_destination.ok_or(ErrorCode::MissingPayer)?,
+                token_program,
+            )?;
+        }
+    }
+
+    team_vault.vesting_founders.remove(position);
+
+    Ok(())
+}
+
+/// Point the accounts that record a founder's old key at their new one: the
+/// founder's vesting pays out to the new key, the disputes they opened can be
+/// cancelled by it, and their votes on every open dispute count for it, so the
+/// new key cannot vote a second time
+pub fn move_founder_records<'info>(
+    team_vault: &Account<'info, TeamVault>,
+    founder_vesting: Option<&mut Account<'info, FounderVesting>>,
+    open_disputes: &'info [AccountInfo<'info>],
+    old_founder: &Pubkey,
+    new_founder: Pubkey,
+) -> Result<()> {
+    if team_vault.vesting_founders.contains(old_founder) {
+        let founder_vesting = founder_vesting.ok_or(ErrorCode::MissingFounderVesting)?;
+        require!(
+            founder_vesting.beneficiary == *old_founder,
+            ErrorCode::MissingFounderVesting
+        );
+        founder_vesting.beneficiary = new_founder;
+    }
+
+    require!(
+        open_disputes.len() == team_vault.open_disputes as usize,
+        ErrorCode::MissingOpenDisputes
+    );
+    for (index, dispute_info) in open_disputes.iter().enumerate() {
+        require!(
+            !open_disputes[..index].iter().any(|other| other.key == dispute_info.key),
+            ErrorCode::MissingOpenDisputes
+        );
+
+        let mut dispute = Account::<Dispute>::try_from(dispute_info)?;
+        require!(
+            dispute.team_vault == team_vault.key() && dispute.status == DisputeStatus::Open,
+            ErrorCode::MissingOpenDisputes
+        );
+        if dispute.initiator == *old_founder {
+            dispute.initiator = new_founder;
+        }
+        for vote in dispute.votes.iter_mut() {
+            if vote.voter == *old_founder {
+                vote.voter = new_founder;
+            }
+        }
+        dispute.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
+
+/// Close a vesting with nothing left to vest or claim, along with its empty
+/// token account, so the founder can be given a new vesting later
+pub fn close_founder_vesting<'info>(
+    founder_vesting: &Account<'info, FounderVesting>,
+    vesting_token_account: &Account<'info, TokenAccount>,
+    rent_destination: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let seeds = &[
+        b"founder_vesting",
+        founder_vesting.team_vault.as_ref(),
+        founder_vesting.founder.as_ref(),
+        &[founder_vesting.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vesting_token_account.to_account_info(),
+            destination: rent_destination.clone(),
+            authority: founder_vesting.to_account_info(),
+        },
+        signer,
+    ))?;
+
+    emit!(FounderVestingClosed {
+        founder_vesting: founder_vesting.key(),
+        team_vault: founder_vesting.team_vault,
+        founder: founder_vesting.founder,
+    });
+
+    founder_vesting.close(rent_destination)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentStream {
+    pub team_vault: Pubkey,
+    pub proposal: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub rate_per_second: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub withdrawn: u64,
+    pub cancelled_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl PaymentStream {
+    /// Amount vested at `now`, nothing before the cliff and frozen once cancelled
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        let vesting_end = self.cancelled_at.map_or(self.end_time, |cancelled_at| cancelled_at.min(self.end_time));
+        let vested_until = now.min(vesting_end);
+        if vested_until < self.cliff_time {
+            return Ok(0);
+        }
+
+        let elapsed = vested_until.saturating_sub(self.start_time) as u128;
+        let vested = (self.rate_per_second as u128)
+            .checked_mul(elapsed)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        u64::try_from(vested).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// `bps` basis points of `amount`, rounded down
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / 10_000;
+
+    u64::try_from(share).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Sum of the milestones paying a fixed amount
+pub fn fixed_milestone_total(milestones: &[Milestone]) -> Result<u64> {
+    milestones
+        .iter()
+        .try_fold(0u64, |total, m| match m.share {
+            MilestoneShare::Amount(amount) => total.checked_add(amount),
+            MilestoneShare::Bps(_) => Some(total),
+        })
+        .ok_or(error!(ErrorCode::MathOverflow))
+}
+
+/// Whether two founder lists contain the same keys, ignoring order
+pub fn same_founders(a: &[Pubkey], b: &[Pubkey]) -> bool {
+    a.len() == b.len() && a.iter().all(|founder| b.contains(founder))
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FounderAgreement {
+    #[max_len(MAX_ID_LEN)]
+    pub team_id: String,
+    pub creator: Pubkey, // Part of the PDA seeds, so nobody can squat another founder's version
+    pub version: u32,
+    pub document_hash: [u8; 32], // Hash of the agreement document (e.g. IPFS content)
+    #[max_len(MAX_FOUNDERS)]
+    pub founders: Vec<Pubkey>,   // Founders who must sign
+    #[max_len(MAX_FOUNDERS)]
+    pub signatures: Vec<AgreementSignature>,
+    pub status: AgreementStatus,
+    pub created_at: i64,
+    pub fully_signed_at: Option<i64>,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Dispute {
+    pub team_vault: Pubkey,
+    pub index: u64,
+    pub initiator: Pubkey,
+    pub dispute_type: DisputeType,
+    pub evidence_hash: [u8; 32], // Hash of the off-chain evidence bundle
+    pub freezes_vault: bool,
+    pub arbitrator: Pubkey,
+    #[max_len(MAX_FOUNDERS)]
+    pub votes: Vec<Vote>,
+    pub status: DisputeStatus,
+    pub resolved_by_arbitrator: bool,
+    pub created_at: i64,
+    pub voting_deadline: i64,
+    pub resolved_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl Dispute {
+    /// Close out the dispute. A decided dispute lifts its freeze on the vault
+    /// now; a cancelled one leaves it to run to the voting deadline, so
+    /// cancelling and reopening cannot keep the vault frozen. The freeze entry
+    /// stays either way to hold the initiator's cooldown.
+    pub fn resolve(&mut self, team_vault: &mut TeamVault, status: DisputeStatus, by_arbitrator: bool, now: i64) {
+        if self.freezes_vault && status != DisputeStatus::Cancelled {
+            for freeze in team_vault.freezes.iter_mut() {
+                if freeze.initiator == self.initiator {
+                    freeze.until = freeze.until.min(now);
+                }
+            }
+        }
+        self.status = status;
+        self.resolved_by_arbitrator = by_arbitrator;
+        self.resolved_at = Some(now);
+        team_vault.open_disputes = team_vault.open_disputes.saturating_sub(1);
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SponsorEscrow {
+    #[max_len(MAX_ID_LEN)]
+    pub quest_id: String,
+    pub sponsor: Pubkey,
+    pub mint: Pubkey, // Mint of the escrowed token account
+    pub total_amount: u64, // Sum of all contributions so far
+    pub released_amount: u64,
+    pub refunded_amount: u64, // Returned to the sponsor by cancellation or expiry
+    pub reserved_amount: u64, // Held for merkle distribution claims
+    #[max_len(MAX_CONTRIBUTORS)]
+    pub contributions: Vec<Contribution>, // Sponsor first, then co-sponsors
+    pub min_contribution: u64, // Least a new co-sponsor may put in
+    #[max_len(MAX_CONTRIBUTORS)]
+    pub owed_refunds: Vec<Contribution>, // Refunds held back for missing or frozen token accounts
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+    #[max_len(MAX_JUDGES)]
+    pub judges: Vec<Pubkey>,
+    pub judge_threshold: u8, // Judge approvals each milestone needs before release
+    #[max_len(MAX_RECIPIENTS)]
+    pub recipients: Vec<Pubkey>, // Wallets milestone payouts may be sent to
+    #[max_len(MAX_JUDGES)]
+    pub cancel_approvals: Vec<Pubkey>, // Judges who consented to cancelling
+    pub prize_table: PrizeTable,
+    #[max_len(MAX_JUDGES)]
+    pub result_votes: Vec<ResultVote>, // Each judge's latest ranked list of winners
+    #[max_len(MAX_PRIZE_RANKS)]
+    pub prizes: Vec<Prize>, // Allocations by rank once results are final
+    pub results_finalized_at: Option<i64>,
+    pub prizes_settled: bool, // Prize pool fully claimed or reclaimed
+    pub status: EscrowStatus,
+    pub bump: u8,
+    pub created_at: i64,
+}
+
+impl SponsorEscrow {
+    /// Part of the escrow paid out by milestone rather than by prize rank
+    pub fn milestone_pool(&self) -> Result<u64> {
+        self.total_amount
+            .checked_sub(self.prize_table.pool)
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    /// Amount a milestone pays out. The last milestone to settle takes
+    /// whatever the others left, so rounding dust never stays in the escrow.
+    pub fn milestone_amount(&self, index: usize) -> Result<u64> {
+        let milestone_pool = self.milestone_pool()?;
+
+        let is_last = self
+            .milestones
+            .iter()
+            .enumerate()
+            .all(|(other, m)| other == index || m.released || m.refunded);
+        if is_last {
+            let settled = self
+                .milestones
+                .iter()
+                .try_fold(0u64, |settled, m| settled.checked_add(m.settled_amount))
+                .ok_or(ErrorCode::MathOverflow)?;
+            return milestone_pool
+                .checked_sub(settled)
+                .ok_or(error!(ErrorCode::MathOverflow));
+        }
+
+        match self.milestones[index].share {
+            MilestoneShare::Amount(amount) => Ok(amount),
+            MilestoneShare::Bps(bps) => {
+                let bps_pool = milestone_pool
+                    .checked_sub(fixed_milestone_total(&self.milestones)?)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                bps_of(bps_pool, bps)
+            }
+        }
+    }
+
+    /// Whether the judges have attested a milestone; such a milestone is owed
+    /// to the recipients and can no longer be reclaimed
+    pub fn is_judge_approved(&self, index: usize) -> bool {
+        !self.judges.is_empty()
+            && self.milestones[index].approvals.len() >= self.judge_threshold as usize
+    }
+
+    /// Record a judge's ranked list of winners, replacing any list they
+    /// submitted before, and return how many judges back that same list
+    pub fn submit_results(&mut self, judge: Pubkey, winners: &[Pubkey]) -> Result<u8> {
+        match self.result_votes.iter_mut().find(|vote| vote.judge == judge) {
+            Some(vote) => {
+                require!(
+                    vote.winners != winners,
+                    ErrorCode::ResultsAlreadyApproved
+                );
+                vote.winners = winners.to_vec();
+            }
+            None => self.result_votes.push(ResultVote {
+                judge,
+                winners: winners.to_vec(),
+            }),
+        }
+
+        Ok(self
+            .result_votes
+            .iter()
+            .filter(|vote| vote.winners == winners)
+            .count() as u8)
+    }
+
+    /// End of the prize claim window, once results are final
+    pub fn claim_deadline(&self) -> Option<i64> {
+        self.results_finalized_at
+            .and_then(|finalized_at| finalized_at.checked_add(self.prize_table.claim_window))
+    }
+
+    /// Whether the judges let the results deadline pass without finalizing
+    pub fn results_overdue(&self, now: i64) -> bool {
+        !self.prize_table.rank_bps.is_empty()
+            && self.results_finalized_at.is_none()
+            && now >= self.prize_table.results_deadline
+    }
+
+    /// Part of the prize pool not yet claimed by a winner
+    pub fn unclaimed_prize_amount(&self) -> Result<u64> {
+        let claimed = self
+            .prizes
+            .iter()
+            .filter(|prize| prize.claimed_at.is_some())
+            .try_fold(0u64, |claimed, prize| claimed.checked_add(prize.amount))
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        self.prize_table
+            .pool
+            .checked_sub(claimed)
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    /// Funds neither released, refunded nor reserved for distribution claims
+    pub fn remaining_amount(&self) -> Result<u64> {
+        self.total_amount
+            .checked_sub(self.released_amount)
+            .and_then(|remaining| remaining.checked_sub(self.refunded_amount))
+            .and_then(|remaining| remaining.checked_sub(self.reserved_amount))
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    /// Split a refund across the contributions pro rata to what each put in.
+    /// The last contributor takes the rounding dust, so the split is exact.
+    pub fn refund_shares(&self, amount: u64) -> Result<Vec<Contribution>> {
+        let mut refunded = 0u64;
+        self.contributions
+            .iter()
+            .enumerate()
+            .map(|(index, contribution)| {
+                let share = if index == self.contributions.len() - 1 {
+                    amount
+                        .checked_sub(refunded)
+                        .ok_or(ErrorCode::MathOverflow)?
+                } else {
+                    let share = (amount as u128)
+                        .checked_mul(contribution.amount as u128)
+                        .ok_or(ErrorCode::MathOverflow)?
+                        / self.total_amount as u128;
+                    u64::try_from(share).map_err(|_| error!(ErrorCode::MathOverflow))?
+                };
+                refunded = refunded
+                    .checked_add(share)
+                    .ok_or(ErrorCode::MathOverflow)?;
+
+                Ok(Contribution {
+                    contributor: contribution.contributor,
+                    amount: share,
+                })
+            })
+            .collect()
+    }
+
+    /// Hold back a refund the contributor's token account could not take
+    pub fn owe_refund(&mut self, contributor: Pubkey, amount: u64) -> Result<()> {
+        match self.owed_refunds.iter_mut().find(|owed| owed.contributor == contributor) {
+            Some(owed) => {
+                owed.amount = owed
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(ErrorCode::MathOverflow)?;
+            }
+            None => self.owed_refunds.push(Contribution {
+                contributor,
+                amount,
+            }),
+        }
+
+        Ok(())
+    }
+
+    /// Whether every milestone, distribution and the prize pool have been paid
+    /// out or refunded
+    pub fn is_settled(&self) -> bool {
+        self.milestones.iter().all(|m| m.released || m.refunded)
+            && self.reserved_amount == 0
+            && self.prizes_settled
+    }
+}
+
+/// Pay a refund out of the escrow to its contributors pro rata, into their
+/// token accounts passed in ledger order. Any account may stand in for a
+/// contributor whose share is zero. A share whose associated token account is
+/// missing or frozen stays in the escrow as an owed refund, so one contributor
+/// cannot block the others.
+pub fn refund_contributors<'info>(
+    escrow: &mut Account<'info, SponsorEscrow>,
+    escrow_token_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    contributor_accounts: &'info [AccountInfo<'info>],
+    amount: u64,
+) -> Result<Vec<Contribution>> {
+    require!(
+        contributor_accounts.len() == escrow.contributions.len(),
+        ErrorCode::MissingTokenAccount
+    );
+
+    let quest_id = escrow.quest_id.clone();
+    let seeds = &[
+        b"sponsor_escrow",
+        quest_id.as_bytes(),
+        &[escrow.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let refunds = escrow.refund_shares(amount)?;
+    for (refund, contributor_info) in refunds.iter().zip(contributor_accounts.iter()) {
+        if refund.amount == 0 {
+            continue;
+        }
+
+        if is_unpayable_recipient(contributor_info, &refund.contributor, &escrow.mint) {
+            escrow.owe_refund(refund.contributor, refund.amount)?;
+            emit!(RefundOwed {
+                escrow: escrow.key(),
+                contributor: refund.contributor,
+                amount: refund.amount,
+            });
+            continue;
+        }
+
+        let contributor_token_account = Account::<TokenAccount>::try_from(contributor_info)?;
+        require!(
+            contributor_token_account.owner == refund.contributor
+                && contributor_token_account.mint == escrow.mint,
+            ErrorCode::InvalidRecipientTokenAccount
+        );
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: escrow_token_account.to_account_info(),
+                    to: contributor_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer,
+            ),
+            refund.amount,
+        )?;
+    }
+
+    Ok(refunds)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MilestoneDistribution {
+    pub escrow: Pubkey,
+    pub milestone_index: u8,
+    pub merkle_root: [u8; 32], // Root over `distribution_leaf` hashes
+    pub total_amount: u64,     // Milestone amount the claims may add up to
+    pub claimed_amount: u64,
+    pub claimant_count: u32,
+    pub expires_at: i64, // After this the unclaimed balance returns to the sponsor
+    #[max_len(DISTRIBUTION_BITMAP_LEN)]
+    pub claimed_bitmap: Vec<u8>, // One bit per leaf index
+    pub bump: u8,
+}
+
+impl MilestoneDistribution {
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed_bitmap[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed_bitmap[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+/// Leaf hash for a claimant's share of a milestone distribution. The leading
+/// zero byte keeps leaves from being passed off as inner nodes.
+pub fn distribution_leaf(index: u32, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[&[0], &index.to_le_bytes(), claimant.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Whether `proof` links `leaf` to `root`, hashing each pair of nodes in
+/// sorted order
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (left, right) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        keccak::hashv(&[&[1], &left, &right]).to_bytes()
+    });
+
+    computed == *root
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    pub authority: Pubkey, // Creates territories
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Territory {
+    #[max_len(MAX_ID_LEN)]
+    pub territory_id: String,
+    #[max_len(MAX_TITLE_LEN)]
+    pub name: String,
+    #[max_len(MAX_DESCRIPTION_LEN)]
+    pub description: String,
+    pub coordinates: [i32; 2],
+    pub size: u32,
+    pub difficulty: u8,
+    pub max_teams: u16,
+    pub current_teams: u16,
+    #[max_len(MAX_URI_LEN)]
+    pub uri: String,
+    pub owner: Option<Pubkey>,
+    pub authority: Pubkey, // Game authority that resolves battles
+    pub battles_won: u32,
+    pub battles_lost: u32,
+    pub total_rewards: u64,
+    pub is_active: bool,
+    pub bump: u8,
+    pub created_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Battle {
+    pub territory: Pubkey,
+    pub challenger: Pubkey,
+    #[max_len(MAX_ID_LEN)]
+    pub challenger_team_id: String,
+    pub defender: Option<Pubkey>,
+    pub battle_type: BattleType,
+    pub status: BattleStatus,
+    pub stake_amount: u64,
+    pub winner: Option<Pubkey>,
+    pub score: u32,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub resolved_at: Option<i64>,
+}
+
+// =============================================================================
+// DATA TYPES
+// =============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Vote {
+    pub voter: Pubkey,
+    pub delegator: Option<Pubkey>, // Founder the voter cast this vote for as delegate
+    pub support: bool,
+    pub timestamp: i64,
+}
+
+impl Vote {
+    /// Founder whose weight this vote carries
+    pub fn founder(&self) -> Pubkey {
+        self.delegator.unwrap_or(self.voter)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct VoteDelegation {
+    pub founder: Pubkey,
+    pub delegate: Pubkey, // Another founder or an outside key
+    pub expires_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct Payout {
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct AgreementSignature {
+    pub founder: Pubkey,
+    pub signed_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct SeparationShare {
+    pub founder: Pubkey,
+    pub share_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct VaultBalance {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct VaultFreeze {
+    pub initiator: Pubkey,
+    pub until: i64, // The dispute's voting deadline, or when it was decided
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct SpendingAllowance {
+    pub founder: Pubkey,
+    pub mint: Pubkey,
+    pub amount_per_period: u64,
+    pub period: i64, // Seconds before the spent amount resets
+    pub spent: u64,
+    pub period_start: i64,
+}
+
+impl SpendingAllowance {
+    /// Debit the allowance, starting a new period first if the current one has ended
+    pub fn debit(&mut self, amount: u64, now: i64) -> Result<()> {
+        if now >= self.period_start.saturating_add(self.period) {
+            let elapsed_periods = now.checked_sub(self.period_start).ok_or(ErrorCode::MathOverflow)? / self.period;
+            self.period_start = elapsed_periods
+                .checked_mul(self.period)
+                .and_then(|elapsed| self.period_start.checked_add(elapsed))
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.spent = 0;
+        }
+
+        let spent = self.spent.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            spent <= self.amount_per_period,
+            ErrorCode::AllowanceExceeded
+        );
+        self.spent = spent;
+
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct VaultInstruction {
+    pub program_id: Pubkey,
+    #[max_len(MAX_INSTRUCTION_ACCOUNTS)]
+    pub accounts: Vec<VaultAccountMeta>,
+    #[max_len(MAX_INSTRUCTION_DATA_LEN)]
+    pub data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct VaultAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&VaultInstruction> for Instruction {
+    fn from(vault_instruction: &VaultInstruction) -> Self {
+        Instruction {
+            program_id: vault_instruction.program_id,
+            accounts: vault_instruction
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: vault_instruction.data.clone(),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct GovernanceConfig {
+    pub voting_period: i64,   // Seconds a proposal stays open for votes
+    pub execution_delay: i64, // Seconds between approval and execution
+    pub quorum: Option<u8>,   // Minimum number of founders that must vote
+}
+
+impl GovernanceConfig {
+    pub fn validate(&self, founder_count: usize) -> Result<()> {
+        require!(
+            self.voting_period > 0 && self.voting_period <= MAX_VOTING_PERIOD,
+            ErrorCode::InvalidGovernanceConfig
+        );
+        require!(
+            self.execution_delay >= 0 && self.execution_delay <= MAX_EXECUTION_DELAY,
+            ErrorCode::InvalidGovernanceConfig
+        );
+        if let Some(quorum) = self.quorum {
+            require!(
+                quorum > 0 && quorum as usize <= founder_count,
+                ErrorCode::InvalidGovernanceConfig
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Milestone {
+    #[max_len(MAX_MILESTONE_TITLE_LEN)]
+    pub title: String,
+    #[max_len(MAX_DESCRIPTION_LEN)]
+    pub description: String,
+    pub share: MilestoneShare,
+    pub released: bool,
+    pub released_at: Option<i64>,
+    pub deadline: Option<i64>, // After this an unreleased milestone can be refunded
+    pub refunded: bool,
+    pub settled_amount: u64, // Released, distributed or refunded amount once settled
+    #[max_len(MAX_JUDGES)]
+    pub approvals: Vec<Pubkey>, // Judges who attested the milestone
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum MilestoneShare {
+    Bps(u16),    // Basis points of the milestone pool left after fixed amounts
+    Amount(u64), // Fixed token amount
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PrizeTable {
+    pub pool: u64, // Part of the escrow paid out by rank instead of by milestone
+    #[max_len(MAX_PRIZE_RANKS)]
+    pub rank_bps: Vec<u16>, // Share of the pool for each rank, first place first
+    pub claim_window: i64, // Seconds winners have to claim once results are final
+    pub results_deadline: i64, // After this, unfinalized prizes can be reclaimed without the judges
+}
+
+impl PrizeTable {
+    pub fn validate(&self, total_amount: u64, now: i64) -> Result<()> {
+        if self.rank_bps.is_empty() {
+            require!(
+                self.pool == 0,
+                ErrorCode::InvalidPrizeTable
+            );
+            return Ok(());
+        }
+
+        require!(
+            self.pool > 0
+                && self.pool <= total_amount
+                && self.claim_window > 0
+                && self.claim_window <= MAX_CLAIM_WINDOW
+                && self.results_deadline > now
+                && self.results_deadline <= now.saturating_add(MAX_RESULTS_PERIOD)
+                && self.rank_bps.len() <= MAX_PRIZE_RANKS
+                && self.rank_bps.iter().all(|bps| *bps > 0)
+                && self.rank_bps.iter().map(|bps| *bps as u32).sum::<u32>() == 10_000,
+            ErrorCode::InvalidPrizeTable
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Contribution {
+    pub contributor: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ResultVote {
+    pub judge: Pubkey,
+    #[max_len(MAX_PRIZE_RANKS)]
+    pub winners: Vec<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Prize {
+    pub winner: Pubkey,
+    pub amount: u64,
+    pub claimed_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum ProposalType {
+    Transfer,
+    BatchTransfer {
+        #[max_len(MAX_PAYOUTS)]
+        payouts: Vec<Payout>,
+    },
+    AddFounder { founder: Pubkey, weight_bps: u16 },
+    RemoveFounder { founder: Pubkey },
+    ChangeThreshold { threshold: u8 },
+    SetFounderWeights {
+        #[max_len(MAX_FOUNDERS)]
+        founder_weights: Option<Vec<u16>>,
+        threshold: u8,
+    },
+    RenameTeam {
+        #[max_len(MAX_TEAM_NAME_LEN)]
+        name: String,
+    },
+    UpdateGovernance { config: GovernanceConfig },
+    ExecuteInstructions {
+        #[max_len(MAX_VAULT_INSTRUCTIONS)]
+        instructions: Vec<VaultInstruction>,
+    },
+    SetAllowedPrograms {
+        #[max_len(MAX_ALLOWED_PROGRAMS)]
+        programs: Vec<Pubkey>,
+    },
+    SetAllowance { founder: Pubkey, mint: Pubkey, amount_per_period: u64, period: i64 },
+    CreatePaymentStream {
+        recipient: Pubkey,
+        mint: Pubkey,
+        rate_per_second: u64,
+        start_time: i64,
+        cliff_time: i64,
+        end_time: i64,
+    },
+    CancelPaymentStream { payment_stream: Pubkey },
+    SeparateTeam {
+        #[max_len(MAX_FOUNDERS)]
+        distribution: Vec<SeparationShare>,
+    },
+    SetArbitrator { arbitrator: Option<Pubkey> },
+    CreateFounderVesting {
+        founder: Pubkey,
+        mint: Pubkey,
+        total_amount: u64,
+        start_time: i64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+        revocable: bool,
+    },
+    RecoverFounder { lost_founder: Pubkey, new_founder: Pubkey },
+}
+
+impl ProposalType {
+    /// Whether executing this proposal changes the vault configuration
+    pub fn is_governance_change(&self) -> bool {
+        !matches!(
+            self,
+            ProposalType::Transfer
+                | ProposalType::BatchTransfer { .. }
+                | ProposalType::ExecuteInstructions { .. }
+                | ProposalType::SetAllowance { .. }
+                | ProposalType::CreatePaymentStream { .. }
+                | ProposalType::CancelPaymentStream { .. }
+                | ProposalType::CreateFounderVesting { .. }
+                | ProposalType::SeparateTeam { .. }
+        )
+    }
+
+    /// Whether this proposal waits for an out-of-date founder agreement to be
+    /// signed again. Recovering or removing a founder does not: a founder who
+    /// lost their key can never sign, and these are how the others get past
+    /// that.
+    pub fn needs_current_agreement(&self) -> bool {
+        self.is_governance_change()
+            && !matches!(
+                self,
+                ProposalType::RecoverFounder { .. } | ProposalType::RemoveFounder { .. }
+            )
+    }
+
+    /// Founder whose key this proposal replaces, who has no say in the outcome
+    /// beyond a veto
+    pub fn lost_founder(&self) -> Option<Pubkey> {
+        match self {
+            ProposalType::RecoverFounder { lost_founder, .. } => Some(*lost_founder),
+            _ => None,
+        }
+    }
+
+    /// Founder whose vesting account this proposal creates, used for PDA seeds.
+    /// Other proposal types refuse a new vesting account outright.
+    pub fn vesting_founder(&self) -> Pubkey {
+        match self {
+            ProposalType::CreateFounderVesting { founder, .. } => *founder,
+            _ => Pubkey::default(),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum ProposalStatus {
+    Pending,
+    Approved,
+    PartiallyExecuted,
+    Rejected,
     Executed,
     Expired,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum AgreementStatus {
+    PendingSignatures,
+    FullySigned,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum DisputeType {
+    ResourceAllocation,
+    DecisionMaking,
+    EquitySplit,
+    TeamSeparation,
+    BreachOfAgreement,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum DisputeStatus {
+    Open,
+    Approved,
+    Rejected,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum EscrowStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum BattleType {
+    Conquest,
+    Defense,
+    Raid,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum BattleStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+// =============================================================================
+// CONTEXT STRUCTURES
+// =============================================================================
+
+#[derive(Accounts)]
+#[instruction(team_id: String)]
+pub struct InitializeTeamVault<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TeamVault::INIT_SPACE,
+        seeds = [b"team_vault", team_id.as_bytes()],
+        bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        seeds = [
+            b"founder_agreement",
+            founder_agreement.team_id.as_bytes(),
+            founder_agreement.creator.as_ref(),
+            &founder_agreement.version.to_le_bytes()
+        ],
+        bump = founder_agreement.bump
+    )]
+    pub founder_agreement: Option<Account<'info, FounderAgreement>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositToVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        token::authority = team_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = vault_token_account.mint,
+        token::authority = contributor,
+    )]
+    pub contributor_token_account: Account<'info, TokenAccount>,
+    
+    pub contributor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [
+            b"proposal",
+            team_vault.key().as_ref(),
+            &team_vault.proposal_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteOnProposal<'info> {
+    #[account(
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"proposal", team_vault.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitSignedVotes<'info> {
+    #[account(
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"proposal", team_vault.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// CHECK: address is constrained to the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"proposal", team_vault.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        token::authority = team_vault,
+        constraint = proposal.mint.is_none() || proposal.mint == Some(vault_token_account.mint)
+            @ ErrorCode::InvalidProposalMint
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        token::authority = proposal.recipient,
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentStream::INIT_SPACE,
+        seeds = [b"payment_stream", proposal.key().as_ref()],
+        bump,
+        constraint = matches!(proposal.proposal_type, ProposalType::CreatePaymentStream { .. })
+            @ ErrorCode::UnexpectedAccount
+    )]
+    pub new_payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"payment_stream", payment_stream.proposal.as_ref()],
+        bump = payment_stream.bump
+    )]
+    pub payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FounderVesting::INIT_SPACE,
+        seeds = [
+            b"founder_vesting",
+            team_vault.key().as_ref(),
+            proposal.proposal_type.vesting_founder().as_ref()
+        ],
+        bump,
+        constraint = matches!(proposal.proposal_type, ProposalType::CreateFounderVesting { .. })
+            @ ErrorCode::UnexpectedAccount
+    )]
+    pub new_founder_vesting: Option<Account<'info, FounderVesting>>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"founder_vesting", team_vault.key().as_ref(), founder_vesting.founder.as_ref()],
+        bump = founder_vesting.bump
+    )]
+    pub founder_vesting: Option<Account<'info, FounderVesting>>,
+    
+    #[account(mut)]
+    pub vesting_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct PaySkippedPayout<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"proposal", team_vault.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        token::authority = team_vault,
+        constraint = proposal.mint == Some(vault_token_account.mint) @ ErrorCode::InvalidProposalMint
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SweepSeparatedVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        has_one = team_vault,
+        seeds = [b"proposal", team_vault.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        token::authority = team_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTeamVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    pub founder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(team_id: String, version: u32)]
+pub struct CreateFounderAgreement<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + FounderAgreement::INIT_SPACE,
+        seeds = [
+            b"founder_agreement",
+            team_id.as_bytes(),
+            creator.key().as_ref(),
+            &version.to_le_bytes()
+        ],
+        bump
+    )]
+    pub founder_agreement: Account<'info, FounderAgreement>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SignAgreement<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"founder_agreement",
+            founder_agreement.team_id.as_bytes(),
+            founder_agreement.creator.as_ref(),
+            &founder_agreement.version.to_le_bytes()
+        ],
+        bump = founder_agreement.bump
+    )]
+    pub founder_agreement: Account<'info, FounderAgreement>,
+    
+    pub founder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdoptAgreement<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        seeds = [
+            b"founder_agreement",
+            team_vault.team_id.as_bytes(),
+            founder_agreement.creator.as_ref(),
+            &founder_agreement.version.to_le_bytes()
+        ],
+        bump = founder_agreement.bump
+    )]
+    pub founder_agreement: Account<'info, FounderAgreement>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        init,
+        payer = initiator,
+        space = 8 + Dispute::INIT_SPACE,
+        seeds = [b"dispute", team_vault.key().as_ref(), &team_vault.dispute_count.to_le_bytes()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    #[account(mut)]
+    pub initiator: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum EscrowStatus {
-    Active,
-    Completed,
-    Cancelled,
+#[derive(Accounts)]
+pub struct VoteOnDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"dispute", team_vault.key().as_ref(), &dispute.index.to_le_bytes()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    pub voter: Signer<'info>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum BattleType {
-    Conquest,
-    Defense,
-    Raid,
+#[derive(Accounts)]
+pub struct ArbitrateDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"dispute", team_vault.key().as_ref(), &dispute.index.to_le_bytes()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    pub arbitrator: Signer<'info>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum BattleStatus {
-    Pending,
-    InProgress,
-    Completed,
-    Cancelled,
+#[derive(Accounts)]
+pub struct CancelDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"dispute", team_vault.key().as_ref(), &dispute.index.to_le_bytes()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    pub initiator: Signer<'info>,
 }
 
-// =============================================================================
-// CONTEXT STRUCTURES
-// =============================================================================
+#[derive(Accounts)]
+pub struct ClaimVestedTokens<'info> {
+    #[account(
+        mut,
+        address = founder_vesting.team_vault,
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        constraint = founder_vesting.beneficiary == founder.key() @ ErrorCode::UnauthorizedFounder,
+        seeds = [b"founder_vesting", founder_vesting.team_vault.as_ref(), founder_vesting.founder.as_ref()],
+        bump = founder_vesting.bump
+    )]
+    pub founder_vesting: Account<'info, FounderVesting>,
+    
+    #[account(
+        mut,
+        token::mint = founder_vesting.mint,
+        token::authority = founder_vesting,
+    )]
+    pub vesting_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = founder_vesting.mint,
+        token::authority = founder,
+    )]
+    pub founder_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub founder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
 
 #[derive(Accounts)]
-#[instruction(team_id: String)]
-pub struct InitializeTeamVault<'info> {
+pub struct WithdrawFromStream<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        has_one = recipient,
+        seeds = [b"payment_stream", payment_stream.proposal.as_ref()],
+        bump = payment_stream.bump
+    )]
+    pub payment_stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        token::mint = payment_stream.mint,
+        token::authority = team_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = payment_stream.mint,
+        token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    
+    pub recipient: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SpendFromAllowance<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        token::authority = team_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = vault_token_account.mint,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    
+    pub founder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ManageDelegation<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    pub founder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RotateFounderKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"team_vault", team_vault.team_id.as_bytes()],
+        bump = team_vault.bump
+    )]
+    pub team_vault: Account<'info, TeamVault>,
+    
+    #[account(
+        mut,
+        has_one = team_vault,
+        seeds = [b"founder_vesting", team_vault.key().as_ref(), founder_vesting.founder.as_ref()],
+        bump = founder_vesting.bump
+    )]
+    pub founder_vesting: Option<Account<'info, FounderVesting>>,
+    
+    pub founder: Signer<'info>,
+    pub new_founder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.team_vault.as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(
+        mut,
+        has_one = proposer @ ErrorCode::UnauthorizedProposer,
+        seeds = [b"proposal", proposal.team_vault.as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(quest_id: String)]
+pub struct InitializeSponsorEscrow<'info> {
     #[account(
         init,
-        payer = authority,
-        space = 8 + 32 + 100 + 32 * 10 + 1 + 8 + 8 + 1 + 8 + 1,
-        seeds = [b"team_vault", team_id.as_bytes()],
+        payer = sponsor,
+        space = 8 + SponsorEscrow::INIT_SPACE,
+        seeds = [b"sponsor_escrow", quest_id.as_bytes()],
         bump
     )]
-    pub team_vault: Account<'info, TeamVault>,
+    pub escrow: Account<'info, SponsorEscrow>,
     
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub sponsor: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::authority = sponsor,
+    )]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = sponsor_token_account.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateProposal<'info> {
-    #[account(mut)]
-    pub team_vault: Account<'info, TeamVault>,
+pub struct ReleaseMilestone<'info> {
+    #[account(
+        mut,
+        has_one = sponsor @ ErrorCode::UnauthorizedSponsor,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        constraint = escrow.recipients.contains(&recipient_token_account.owner) @ ErrorCode::UnregisteredRecipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    
+    pub sponsor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestone_index: u8)]
+pub struct DistributeMilestone<'info> {
+    #[account(
+        mut,
+        has_one = sponsor @ ErrorCode::UnauthorizedSponsor,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
     
     #[account(
         init,
-        payer = proposer,
-        space = 8 + 32 + 32 + 100 + 200 + 32 + 8 + 1 + 32 * 10 + 1 + 8 + 8 + 8,
+        payer = sponsor,
+        space = 8 + MilestoneDistribution::INIT_SPACE,
+        seeds = [b"milestone_distribution", escrow.key().as_ref(), &[milestone_index]],
+        bump
+    )]
+    pub distribution: Account<'info, MilestoneDistribution>,
+    
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDistribution<'info> {
+    #[account(
+        mut,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
+    
+    #[account(
+        mut,
+        has_one = escrow,
+        seeds = [b"milestone_distribution", escrow.key().as_ref(), &[distribution.milestone_index]],
+        bump = distribution.bump
+    )]
+    pub distribution: Account<'info, MilestoneDistribution>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = claimant,
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+    
+    pub claimant: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimDistribution<'info> {
+    #[account(
+        mut,
+        has_one = sponsor,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
+    
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = escrow,
+        seeds = [b"milestone_distribution", escrow.key().as_ref(), &[distribution.milestone_index]],
+        bump = distribution.bump
+    )]
+    pub distribution: Account<'info, MilestoneDistribution>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Escrow sponsor receiving the distribution's rent, matched by `has_one`
+    #[account(mut)]
+    pub sponsor: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct JudgeEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
+    
+    pub judge: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelEscrow<'info> {
+    #[account(
+        mut,
+        has_one = sponsor @ ErrorCode::UnauthorizedSponsor,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    pub sponsor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = contributor,
+    )]
+    pub contributor_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
     )]
-    pub proposal: Account<'info, Proposal>,
+    pub escrow_token_account: Account<'info, TokenAccount>,
     
-    #[account(mut)]
-    pub proposer: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    pub contributor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct VoteOnProposal<'info> {
-    pub team_vault: Account<'info, TeamVault>,
+pub struct ReclaimToContributors<'info> {
+    #[account(
+        mut,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
     
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
     
-    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct ExecuteProposal<'info> {
-    pub team_vault: Account<'info, TeamVault>,
-    
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
+pub struct PayOwedRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, SponsorEscrow>,
     
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
     
     #[account(mut)]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub contributor_token_account: Account<'info, TokenAccount>,
     
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-#[instruction(quest_id: String)]
-pub struct InitializeSponsorEscrow<'info> {
+pub struct ClaimPrize<'info> {
     #[account(
-        init,
-        payer = sponsor,
-        space = 8 + 32 + 32 + 8 + 8 + 32 * 10 + 1 + 1 + 8,
-        seeds = [b"sponsor_escrow", quest_id.as_bytes()],
-        bump
+        mut,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
     )]
     pub escrow: Account<'info, SponsorEscrow>,
     
-    #[account(mut)]
-    pub sponsor: Signer<'info>,
-    
-    #[account(mut)]
-    pub sponsor_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
     pub escrow_token_account: Account<'info, TokenAccount>,
     
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = winner,
+    )]
+    pub winner_token_account: Account<'info, TokenAccount>,
+    
+    pub winner: Signer<'info>,
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ReleaseMilestone<'info> {
-    #[account(mut)]
+pub struct CloseEscrow<'info> {
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = sponsor @ ErrorCode::UnauthorizedSponsor,
+        seeds = [b"sponsor_escrow", escrow.quest_id.as_bytes()],
+        bump = escrow.bump
+    )]
     pub escrow: Account<'info, SponsorEscrow>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = escrow,
+    )]
     pub escrow_token_account: Account<'info, TokenAccount>,
     
     #[account(mut)]
-    pub recipient_token_account: Account<'info, TokenAccount>,
-    
-    pub authority: Signer<'info>,
+    pub sponsor: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeProtocolConfig<'info> {
+    #[account(
+        init,
+        payer = upgrade_authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::RiseOfFoundersProtocol>,
+    
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key())
+            @ ErrorCode::UnauthorizedProtocolAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub upgrade_authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(territory_id: String)]
 pub struct InitializeTerritory<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedProtocolAuthority
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 100 + 200 + 8 + 4 + 1 + 2 + 2 + 100 + 32 + 4 + 4 + 8 + 1 + 1 + 8,
+        space = 8 + Territory::INIT_SPACE,
         seeds = [b"territory", territory_id.as_bytes()],
         bump
     )]
@@ -701,66 +5966,362 @@ pub struct InitializeTerritory<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct ChallengeTerritory<'info> {
-    pub territory: Account<'info, Territory>,
-    
-    #[account(
-        init,
-        payer = challenger,
-        space = 8 + 32 + 32 + 32 + 32 + 1 + 1 + 8 + 32 + 4 + 8 + 8 + 8,
-    )]
-    pub battle: Account<'info, Battle>,
-    
-    #[account(mut)]
-    pub challenger: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[derive(Accounts)]
+pub struct ChallengeTerritory<'info> {
+    #[account(
+        seeds = [b"territory", territory.territory_id.as_bytes()],
+        bump = territory.bump
+    )]
+    pub territory: Account<'info, Territory>,
+    
+    #[account(
+        init,
+        payer = challenger,
+        space = 8 + Battle::INIT_SPACE,
+    )]
+    pub battle: Account<'info, Battle>,
+    
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveBattle<'info> {
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::UnauthorizedTerritoryAuthority,
+        seeds = [b"territory", territory.territory_id.as_bytes()],
+        bump = territory.bump
+    )]
+    pub territory: Account<'info, Territory>,
+    
+    #[account(
+        mut,
+        has_one = territory,
+    )]
+    pub battle: Account<'info, Battle>,
+    
+    pub authority: Signer<'info>,
+}
+
+// =============================================================================
+// EVENTS
+// =============================================================================
+
+#[event]
+pub struct TeamVaultCreated {
+    pub team_vault: Pubkey,
+    pub team_id: String,
+    pub founders: Vec<Pubkey>,
+    pub founder_weights: Option<Vec<u16>>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct VaultDeposit {
+    pub team_vault: Pubkey,
+    pub contributor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub balance: u64, // Vault balance of `mint` afterwards
+}
+
+#[event]
+pub struct VaultWithdrawal {
+    pub team_vault: Pubkey,
+    pub proposal: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal: Pubkey,
+    pub team_vault: Pubkey,
+    pub proposer: Pubkey,
+    pub amount: u64,
+    pub mint: Option<Pubkey>,
+    pub proposal_type: ProposalType,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub proposal: Pubkey,
+    pub team_vault: Pubkey,
+    pub votes: u8,
+    pub support_weight: u64,
+    pub required_weight: u64,
+}
+
+#[event]
+pub struct BatchTransferProgress {
+    pub proposal: Pubkey,
+    pub team_vault: Pubkey,
+    pub executed_payouts: u8,
+    pub total_payouts: u8,
+}
+
+#[event]
+pub struct BatchPayoutSkipped {
+    pub proposal: Pubkey,
+    pub team_vault: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct VaultInstructionsExecuted {
+    pub proposal: Pubkey,
+    pub team_vault: Pubkey,
+    pub program_ids: Vec<Pubkey>,
+    pub balances: Vec<VaultBalance>, // Tracked balances after reconciling every vault token account
+}
+
+#[event]
+pub struct AllowanceUpdated {
+    pub team_vault: Pubkey,
+    pub proposal: Pubkey,
+    pub founder: Pubkey,
+    pub mint: Pubkey,
+    pub amount_per_period: u64,
+    pub period: i64,
+}
+
+#[event]
+pub struct AllowanceSpent {
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct VoteDelegated {
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+    pub delegate: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct VoteDelegationRevoked {
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct FounderKeyRotated {
+    pub team_vault: Pubkey,
+    pub old_founder: Pubkey,
+    pub new_founder: Pubkey,
+    pub recovered: bool, // Replaced by the other founders rather than by the key holder
+}
+
+#[event]
+pub struct SeparationDistribution {
+    pub team_vault: Pubkey,
+    pub proposal: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TeamSeparationSettled {
+    pub team_vault: Pubkey,
+    pub proposal: Pubkey,
+    pub distribution: Vec<SeparationShare>,
+}
+
+#[event]
+pub struct AgreementCreated {
+    pub founder_agreement: Pubkey,
+    pub team_id: String,
+    pub version: u32,
+    pub document_hash: [u8; 32],
+    pub founders: Vec<Pubkey>,
+}
+
+#[event]
+pub struct AgreementSigned {
+    pub founder_agreement: Pubkey,
+    pub founder: Pubkey,
+    pub signatures: u8,
+}
+
+#[event]
+pub struct AgreementFullySigned {
+    pub founder_agreement: Pubkey,
+    pub team_id: String,
+    pub version: u32,
+    pub document_hash: [u8; 32],
+}
+
+#[event]
+pub struct AgreementAdopted {
+    pub team_vault: Pubkey,
+    pub founder_agreement: Pubkey,
+    pub version: u32,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub dispute: Pubkey,
+    pub team_vault: Pubkey,
+    pub initiator: Pubkey,
+    pub dispute_type: DisputeType,
+    pub evidence_hash: [u8; 32],
+    pub freezes_vault: bool,
+    pub voting_deadline: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub dispute: Pubkey,
+    pub team_vault: Pubkey,
+    pub status: DisputeStatus,
+    pub resolved_by_arbitrator: bool,
+}
+
+#[event]
+pub struct DisputeCancelled {
+    pub dispute: Pubkey,
+    pub team_vault: Pubkey,
+    pub initiator: Pubkey,
+}
+
+#[event]
+pub struct TeamVaultClosed {
+    pub team_vault: Pubkey,
+    pub closed_by: Pubkey,
+    pub refunded: u64, // Rent returned to the founders
+}
+
+#[event]
+pub struct FounderVestingCreated {
+    pub founder_vesting: Pubkey,
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub cliff_duration: i64,
+    pub vesting_duration: i64,
+    pub revocable: bool,
+}
+
+#[event]
+pub struct VestedTokensClaimed {
+    pub founder_vesting: Pubkey,
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+    pub amount: u64,
+    pub claimed: u64,
+}
+
+#[event]
+pub struct FounderVestingClosed {
+    pub founder_vesting: Pubkey,
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+}
+
+#[event]
+pub struct FounderVestingRevoked {
+    pub founder_vesting: Pubkey,
+    pub team_vault: Pubkey,
+    pub founder: Pubkey,
+    pub mint: Pubkey,
+    pub returned_amount: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct PaymentStreamCreated {
+    pub payment_stream: Pubkey,
+    pub team_vault: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub rate_per_second: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct PaymentStreamWithdrawal {
+    pub payment_stream: Pubkey,
+    pub team_vault: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub withdrawn: u64,
+    pub balance: u64,
 }
 
-#[derive(Accounts)]
-pub struct ResolveBattle<'info> {
-    #[account(mut)]
-    pub territory: Account<'info, Territory>,
-    
-    #[account(mut)]
-    pub battle: Account<'info, Battle>,
-    
-    pub authority: Signer<'info>,
+#[event]
+pub struct PaymentStreamCancelled {
+    pub payment_stream: Pubkey,
+    pub team_vault: Pubkey,
+    pub vested_amount: u64,
+    pub withdrawn: u64,
 }
 
-// =============================================================================
-// EVENTS
-// =============================================================================
+#[event]
+pub struct SignedVoteRecorded {
+    pub proposal: Pubkey,
+    pub team_vault: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+}
 
 #[event]
-pub struct TeamVaultCreated {
+pub struct ProposalRejected {
+    pub proposal: Pubkey,
     pub team_vault: Pubkey,
-    pub team_id: String,
-    pub founders: Vec<Pubkey>,
-    pub threshold: u8,
+    pub votes_against: u8,
+    pub against_weight: u64,
+    pub required_weight: u64,
 }
 
 #[event]
-pub struct ProposalCreated {
+pub struct ProposalExpired {
     pub proposal: Pubkey,
     pub team_vault: Pubkey,
-    pub proposer: Pubkey,
-    pub amount: u64,
-    pub proposal_type: ProposalType,
+    pub expires_at: i64,
 }
 
 #[event]
-pub struct ProposalApproved {
+pub struct ProposalCancelled {
     pub proposal: Pubkey,
     pub team_vault: Pubkey,
-    pub votes: u8,
+    pub proposer: Pubkey,
 }
 
 #[event]
 pub struct ProposalExecuted {
     pub proposal: Pubkey,
     pub team_vault: Pubkey,
-    pub amount: u64,
+    pub amount: u64, // Amount actually paid out
+    pub skipped: Vec<Pubkey>, // Batch recipients still owed a skipped payout
+}
+
+#[event]
+pub struct TeamVaultConfigUpdated {
+    pub team_vault: Pubkey,
+    pub proposal: Pubkey,
+    pub name: String,
+    pub founders: Vec<Pubkey>,
+    pub founder_weights: Option<Vec<u16>>,
+    pub threshold: u8,
+    pub governance: GovernanceConfig,
+    pub config_version: u32,
 }
 
 #[event]
@@ -769,15 +6330,152 @@ pub struct EscrowCreated {
     pub quest_id: String,
     pub sponsor: Pubkey,
     pub total_amount: u64,
+    pub judges: Vec<Pubkey>,
+    pub judge_threshold: u8,
+    pub recipients: Vec<Pubkey>,
+    pub prize_table: PrizeTable,
+    pub min_contribution: u64,
+}
+
+#[event]
+pub struct EscrowFunded {
+    pub escrow: Pubkey,
+    pub contributor: Pubkey,
+    pub amount: u64,
+    pub contributed: u64,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct MilestoneApproved {
+    pub escrow: Pubkey,
+    pub milestone_index: u8,
+    pub judge: Pubkey,
+    pub approvals: u8,
+    pub judge_threshold: u8,
 }
 
 #[event]
 pub struct MilestoneReleased {
     pub escrow: Pubkey,
     pub milestone_index: u8,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MilestoneDistributionCommitted {
+    pub escrow: Pubkey,
+    pub distribution: Pubkey,
+    pub milestone_index: u8,
+    pub merkle_root: [u8; 32],
+    pub claimant_count: u32,
+    pub amount: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct DistributionClaimed {
+    pub escrow: Pubkey,
+    pub distribution: Pubkey,
+    pub claimant: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+    pub claimed_amount: u64,
+}
+
+#[event]
+pub struct DistributionReclaimed {
+    pub escrow: Pubkey,
+    pub distribution: Pubkey,
+    pub amount: u64,
+    pub refunds: Vec<Contribution>,
+}
+
+#[event]
+pub struct EscrowCancellationApproved {
+    pub escrow: Pubkey,
+    pub judge: Pubkey,
+    pub approvals: u8,
+    pub judge_threshold: u8,
+}
+
+#[event]
+pub struct EscrowCancelled {
+    pub escrow: Pubkey,
+    pub sponsor: Pubkey,
+    pub refund_amount: u64,
+    pub released_amount: u64,
+    pub refunds: Vec<Contribution>,
+}
+
+#[event]
+pub struct MilestoneRefunded {
+    pub escrow: Pubkey,
+    pub milestone_index: u8,
+    pub amount: u64,
+    pub refunds: Vec<Contribution>,
+}
+
+#[event]
+pub struct ResultsApproved {
+    pub escrow: Pubkey,
+    pub judge: Pubkey,
+    pub winners: Vec<Pubkey>,
+    pub approvals: u8,
+    pub judge_threshold: u8,
+}
+
+#[event]
+pub struct ResultsFinalized {
+    pub escrow: Pubkey,
+    pub prizes: Vec<Prize>,
+    pub claim_deadline: i64,
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub escrow: Pubkey,
+    pub winner: Pubkey,
+    pub rank: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct UnclaimedPrizesReclaimed {
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub refunds: Vec<Contribution>,
+}
+
+#[event]
+pub struct RefundOwed {
+    pub escrow: Pubkey,
+    pub contributor: Pubkey,
     pub amount: u64,
 }
 
+#[event]
+pub struct OwedRefundPaid {
+    pub escrow: Pubkey,
+    pub contributor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct EscrowClosed {
+    pub escrow: Pubkey,
+    pub sponsor: Pubkey,
+    pub leftover: u64,
+    pub refunds: Vec<Contribution>,
+}
+
+#[event]
+pub struct ProtocolConfigInitialized {
+    pub protocol_config: Pubkey,
+    pub authority: Pubkey,
+}
+
 #[event]
 pub struct TerritoryCreated {
     pub territory: Pubkey,
@@ -799,7 +6497,7 @@ pub struct TerritoryChallenge {
 pub struct BattleResolved {
     pub territory: Pubkey,
     pub battle: Pubkey,
-    pub winner: Pubkey,
+    pub winner: Option<Pubkey>, // None when the challenge failed
     pub score: u32,
 }
 
@@ -833,6 +6531,7 @@ pub enum ErrorCode {
     #[msg("Too many milestones (max 10)")]
     TooManyMilestones,
     
+    // No longer returned; kept so the codes after it do not shift
     #[msg("Milestone percentages must sum to 100")]
     InvalidMilestonePercentages,
     
@@ -853,4 +6552,346 @@ pub enum ErrorCode {
     
     #[msg("Battle is not active")]
     BattleNotActive,
+    
+    #[msg("Proposal has not expired yet")]
+    ProposalNotExpired,
+    
+    #[msg("Only the proposer can cancel this proposal")]
+    UnauthorizedProposer,
+    
+    #[msg("Proposal already has votes")]
+    ProposalHasVotes,
+    
+    #[msg("Team vault is not active")]
+    TeamVaultNotActive,
+    
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    
+    #[msg("Insufficient funds in team vault")]
+    InsufficientVaultFunds,
+    
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    
+    #[msg("Founder is already part of the team")]
+    FounderAlreadyExists,
+    
+    #[msg("Founder not found in team")]
+    FounderNotFound,
+    
+    #[msg("Founder weights must be positive, match the founders and sum to at most 10000 bps")]
+    InvalidFounderWeights,
+    
+    #[msg("Invalid team name")]
+    InvalidTeamName,
+    
+    #[msg("Proposal was created under a previous founder configuration")]
+    StaleProposal,
+    
+    #[msg("Invalid governance configuration")]
+    InvalidGovernanceConfig,
+    
+    #[msg("Execution timelock has not elapsed")]
+    TimelockNotElapsed,
+    
+    #[msg("Token account required for this proposal type")]
+    MissingTokenAccount,
+    
+    #[msg("Batch transfers need 1-10 payouts with non-zero amounts")]
+    InvalidPayouts,
+    
+    #[msg("Recipient token account does not match the payout")]
+    InvalidRecipientTokenAccount,
+    
+    #[msg("Vault instructions exceed size limits (max 3, 8 accounts, 128 bytes)")]
+    InvalidVaultInstructions,
+    
+    #[msg("Target program is not allowlisted for this vault")]
+    ProgramNotAllowed,
+    
+    #[msg("Too many allowlisted programs (max 5)")]
+    TooManyAllowedPrograms,
+    
+    #[msg("Allowance needs a positive period")]
+    InvalidAllowance,
+    
+    #[msg("Too many spending allowances (max 10)")]
+    TooManyAllowances,
+    
+    #[msg("No allowance for this founder and mint")]
+    AllowanceNotFound,
+    
+    #[msg("Spend exceeds the remaining allowance for this period")]
+    AllowanceExceeded,
+    
+    #[msg("Payment stream needs a positive rate and start <= cliff <= end")]
+    InvalidPaymentStream,
+    
+    #[msg("Payment stream account missing or does not match the proposal")]
+    MissingPaymentStream,
+    
+    #[msg("Payment stream is already cancelled")]
+    PaymentStreamCancelled,
+    
+    #[msg("Nothing available to withdraw")]
+    NothingToWithdraw,
+    
+    #[msg("Vesting needs a positive amount and duration with 0 <= cliff <= duration")]
+    InvalidVestingSchedule,
+    
+    #[msg("Founder already has a vesting schedule")]
+    FounderVestingExists,
+    
+    #[msg("Founder vesting account missing or does not match")]
+    MissingFounderVesting,
+    
+    #[msg("Vesting token account must be owned by the vesting account and match its mint")]
+    InvalidVestingTokenAccount,
+    
+    #[msg("Vesting schedule is not revocable")]
+    VestingNotRevocable,
+    
+    #[msg("Distribution must list distinct founders with shares summing to 10000 bps")]
+    InvalidDistribution,
+    
+    #[msg("Team vault must be separated and fully drained first")]
+    TeamVaultNotSettled,
+    
+    #[msg("Team vault has no arbitrator configured")]
+    NoArbitrator,
+    
+    #[msg("Arbitrator cannot be a founder")]
+    InvalidArbitrator,
+    
+    #[msg("Only the dispute's arbitrator can arbitrate")]
+    UnauthorizedArbitrator,
+    
+    #[msg("Dispute is not open")]
+    DisputeNotOpen,
+    
+    #[msg("Dispute voting has closed")]
+    DisputeVotingClosed,
+    
+    #[msg("Dispute voting is still open")]
+    DisputeVotingOpen,
+    
+    #[msg("Team vault is frozen by an open dispute")]
+    VaultFrozenByDispute,
+    
+    #[msg("Agreement needs a positive version and distinct founders")]
+    InvalidAgreement,
+    
+    #[msg("Agreement is not awaiting signatures")]
+    AgreementNotPending,
+    
+    #[msg("Founder already signed this agreement")]
+    AlreadySigned,
+    
+    #[msg("Agreement is not fully signed")]
+    AgreementNotFullySigned,
+    
+    #[msg("Agreement does not match the team or its founders")]
+    AgreementMismatch,
+    
+    #[msg("Agreement version is not newer than the adopted one")]
+    AgreementVersionOutdated,
+    
+    #[msg("Founders changed since the adopted agreement; adopt a newly signed one first")]
+    AgreementOutOfDate,
+    
+    #[msg("Missing or malformed ed25519 signed vote")]
+    InvalidSignedVote,
+    
+    #[msg("Delegation is missing, expired or not held by the voter")]
+    InvalidDelegation,
+    
+    #[msg("Founder has no vote delegation")]
+    DelegationNotFound,
+    
+    #[msg("Founder recovery needs at least one other founder")]
+    InvalidFounderRecovery,
+    
+    #[msg("Vault already tracks the maximum number of mints (max 5)")]
+    TooManyVaultMints,
+    
+    #[msg("Account is not used by this proposal type")]
+    UnexpectedAccount,
+    
+    #[msg("Payer required to receive the rent of closed accounts")]
+    MissingPayer,
+    
+    #[msg("Team vault is already closed")]
+    TeamVaultAlreadyClosed,
+    
+    #[msg("Founder already has an open dispute freezing the vault")]
+    FreezeAlreadyOpen,
+    
+    #[msg("Every open dispute of the vault must be passed")]
+    MissingOpenDisputes,
+    
+    #[msg("Transfer proposals must name the mint they pay out in")]
+    MissingProposalMint,
+    
+    #[msg("Vault token account does not hold the proposal's mint")]
+    InvalidProposalMint,
+    
+    #[msg("Team id must be 1-32 bytes")]
+    InvalidTeamId,
+    
+    #[msg("Title is too long")]
+    TitleTooLong,
+    
+    #[msg("Description is too long")]
+    DescriptionTooLong,
+    
+    #[msg("Milestone basis points must sum to 10000, or fixed amounts must cover the milestone pool")]
+    InvalidMilestoneShares,
+    
+    #[msg("Quest id must be 1-32 bytes")]
+    InvalidQuestId,
+    
+    #[msg("Milestone title is too long (max 64 bytes)")]
+    MilestoneTitleTooLong,
+    
+    #[msg("Only the escrow sponsor can manage this escrow")]
+    UnauthorizedSponsor,
+    
+    #[msg("Judges must be distinct (max 5)")]
+    InvalidJudges,
+    
+    #[msg("Judge threshold must be between 1 and the number of judges, or 0 without judges")]
+    InvalidJudgeThreshold,
+    
+    #[msg("Recipients must be distinct (1-10)")]
+    InvalidRecipients,
+    
+    #[msg("Signer is not a judge of this escrow")]
+    UnauthorizedJudge,
+    
+    #[msg("Judge already approved this milestone")]
+    MilestoneAlreadyApproved,
+    
+    #[msg("Milestone lacks the required judge approvals")]
+    MilestoneNotApproved,
+    
+    #[msg("Recipient is not registered on the escrow")]
+    UnregisteredRecipient,
+    
+    #[msg("Milestone deadline must be in the future")]
+    InvalidMilestoneDeadline,
+    
+    #[msg("Milestone was already refunded")]
+    MilestoneAlreadyRefunded,
+    
+    #[msg("Milestone has no deadline or it has not passed yet")]
+    MilestoneNotExpired,
+    
+    #[msg("Judges approved this milestone; it can only be released")]
+    MilestoneAwaitingRelease,
+    
+    #[msg("Judge already approved cancelling this escrow")]
+    CancellationAlreadyApproved,
+    
+    #[msg("Cancellation lacks the required judge approvals")]
+    CancellationNotApproved,
+    
+    #[msg("Escrow is still active")]
+    EscrowStillActive,
+    
+    #[msg("Prize table needs a pool, a claim window and positive rank shares summing to 10000 bps (max 10 ranks)")]
+    InvalidPrizeTable,
+    
+    #[msg("Escrow has no prize table")]
+    NoPrizeTable,
+    
+    #[msg("Winners must be distinct and no more than the prize ranks")]
+    InvalidWinners,
+    
+    #[msg("Judge already submitted these results")]
+    ResultsAlreadyApproved,
+    
+    #[msg("Results are already final")]
+    ResultsAlreadyFinalized,
+    
+    #[msg("Results are not final yet")]
+    ResultsNotFinalized,
+    
+    #[msg("Prize claim window has closed")]
+    ClaimWindowClosed,
+    
+    #[msg("Prize claim window is still open")]
+    ClaimWindowOpen,
+    
+    #[msg("Signer has no prize in this escrow")]
+    NoPrizeForWinner,
+    
+    #[msg("Prize already claimed")]
+    PrizeAlreadyClaimed,
+    
+    #[msg("Prize pool is already settled")]
+    PrizesAlreadySettled,
+    
+    #[msg("Distribution needs 1-4096 claimants and a future expiry")]
+    InvalidMilestoneDistribution,
+    
+    #[msg("Merkle proof does not match the distribution root")]
+    InvalidMerkleProof,
+    
+    #[msg("Leaf already claimed")]
+    DistributionAlreadyClaimed,
+    
+    #[msg("Claims would exceed the milestone amount")]
+    DistributionExceeded,
+    
+    #[msg("Distribution has expired")]
+    DistributionExpired,
+    
+    #[msg("Distribution still has unclaimed funds and has not expired")]
+    DistributionNotExpired,
+    
+    #[msg("Escrow still holds funds for distribution claims")]
+    DistributionOutstanding,
+    
+    #[msg("Escrow has no basis-point milestone left to fund")]
+    NoOpenMilestones,
+    
+    #[msg("Too many escrow contributors (max 10)")]
+    TooManyContributors,
+    
+    #[msg("Contribution is below the escrow's minimum")]
+    ContributionTooSmall,
+    
+    #[msg("Territory id must be 1-32 bytes")]
+    InvalidTerritoryId,
+    
+    #[msg("Territory URI is too long (max 200 bytes)")]
+    UriTooLong,
+    
+    #[msg("Only the territory authority can resolve its battles")]
+    UnauthorizedTerritoryAuthority,
+    
+    #[msg("Battle winner must be the challenger or the defender")]
+    InvalidBattleWinner,
+    
+    #[msg("Only the protocol authority can create territories")]
+    UnauthorizedProtocolAuthority,
+    
+    #[msg("Payout was not skipped or has already been paid")]
+    PayoutNotSkipped,
+    
+    #[msg("Every founder's account must be passed, in order")]
+    MissingFounderAccounts,
+    
+    #[msg("Founder must wait a voting period after their last freeze before freezing again")]
+    FreezeCoolingDown,
+    
+    #[msg("Results deadline has passed")]
+    ResultsDeadlinePassed,
+    
+    #[msg("No refund is owed to this contributor")]
+    NoRefundOwed,
+    
+    #[msg("Owed refunds must be paid before the escrow closes")]
+    RefundsOutstanding,
 }
\ No newline at end of file
@@ -0,0 +1,500 @@
+//! Every context must refuse accounts that belong to another vault, escrow,
+//! territory or signer. Each test first shows the genuine accounts pass
+//! `try_accounts`, then swaps in one substitute and checks it is rejected.
+
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use common::*;
+use rise_of_founders_protocol::{
+    CancelProposal, ClaimVestedTokens, ErrorCode, ExecuteProposal, FounderVesting,
+    ReclaimToContributors, ReleaseMilestone, ResolveBattle, VoteOnDispute, VoteOnProposal,
+    WithdrawFromStream, ID,
+};
+
+// =============================================================================
+// TEAM VAULT
+// =============================================================================
+
+struct ExecuteAccounts {
+    team_vault: AccountInfo<'static>,
+    proposal: AccountInfo<'static>,
+    vault_token_account: AccountInfo<'static>,
+    recipient_token_account: AccountInfo<'static>,
+    payment_stream: AccountInfo<'static>,
+}
+
+impl ExecuteAccounts {
+    fn genuine() -> Self {
+        let vault = vault("builders");
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (proposal_key, mut proposal) = proposal(&vault, recipient);
+        proposal.mint = Some(mint);
+        let (stream_key, stream) = payment_stream(&vault, recipient, mint);
+
+        ExecuteAccounts {
+            team_vault: program_account(vault.key, &vault.account),
+            proposal: program_account(proposal_key, &proposal),
+            vault_token_account: token_account(mint, vault.key),
+            recipient_token_account: token_account(mint, recipient),
+            payment_stream: program_account(stream_key, &stream),
+        }
+    }
+
+    fn try_accounts(self) -> anchor_lang::Result<ExecuteProposal<'static>> {
+        try_accounts(vec![
+            self.team_vault,
+            self.proposal,
+            self.vault_token_account,
+            self.recipient_token_account,
+            none(),
+            self.payment_stream,
+            none(),
+            none(),
+            none(),
+            none(),
+            token_program(),
+            none(),
+        ])
+    }
+}
+
+#[test]
+fn execute_proposal_accepts_genuine_accounts() {
+    assert_accepted(ExecuteAccounts::genuine().try_accounts());
+}
+
+#[test]
+fn execute_proposal_rejects_proposal_of_another_vault() {
+    let other_vault = vault("rivals");
+    let (other_key, other_proposal) = proposal(&other_vault, Pubkey::new_unique());
+
+    let accounts = ExecuteAccounts {
+        proposal: program_account(other_key, &other_proposal),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(accounts.try_accounts(), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn execute_proposal_rejects_vault_copied_outside_its_pda() {
+    let vault = vault("builders");
+    let (proposal_key, mut proposal) = proposal(&vault, Pubkey::new_unique());
+    let impostor = Pubkey::new_unique();
+    proposal.team_vault = impostor;
+
+    let accounts = ExecuteAccounts {
+        team_vault: program_account(impostor, &vault.account),
+        proposal: program_account(proposal_key, &proposal),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(accounts.try_accounts(), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn execute_proposal_rejects_proposal_outside_its_pda() {
+    let vault = vault("builders");
+    let (_, proposal) = proposal(&vault, Pubkey::new_unique());
+
+    let accounts = ExecuteAccounts {
+        team_vault: program_account(vault.key, &vault.account),
+        proposal: program_account(Pubkey::new_unique(), &proposal),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(accounts.try_accounts(), AnchorErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn execute_proposal_rejects_token_account_not_owned_by_vault() {
+    let vault = vault("builders");
+    let (proposal_key, proposal) = proposal(&vault, Pubkey::new_unique());
+    let mint = proposal.mint.unwrap();
+
+    let accounts = ExecuteAccounts {
+        proposal: program_account(proposal_key, &proposal),
+        vault_token_account: token_account(mint, Pubkey::new_unique()),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(
+        accounts.try_accounts(),
+        AnchorErrorCode::ConstraintTokenOwner,
+    );
+}
+
+#[test]
+fn execute_proposal_rejects_vault_token_account_of_another_mint() {
+    let vault = vault("builders");
+    let accounts = ExecuteAccounts {
+        vault_token_account: token_account(Pubkey::new_unique(), vault.key),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(accounts.try_accounts(), ErrorCode::InvalidProposalMint);
+}
+
+#[test]
+fn execute_proposal_rejects_recipient_account_of_someone_else() {
+    let accounts = ExecuteAccounts {
+        recipient_token_account: token_account(Pubkey::new_unique(), Pubkey::new_unique()),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(
+        accounts.try_accounts(),
+        AnchorErrorCode::ConstraintTokenOwner,
+    );
+}
+
+#[test]
+fn execute_proposal_rejects_payment_stream_of_another_vault() {
+    let other_vault = vault("rivals");
+    let (stream_key, stream) =
+        payment_stream(&other_vault, Pubkey::new_unique(), Pubkey::new_unique());
+
+    let accounts = ExecuteAccounts {
+        payment_stream: program_account(stream_key, &stream),
+        ..ExecuteAccounts::genuine()
+    };
+
+    assert_rejected(accounts.try_accounts(), AnchorErrorCode::ConstraintHasOne);
+}
+
+#[test]
+fn vote_on_proposal_rejects_proposal_of_another_vault() {
+    let other_vault = vault("rivals");
+    let (other_key, other_proposal) = proposal(&other_vault, Pubkey::new_unique());
+    let vault = vault("builders");
+    let (proposal_key, proposal) = proposal(&vault, Pubkey::new_unique());
+    let voter = vault.account.founders[0];
+
+    assert_accepted(try_accounts::<VoteOnProposal>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(proposal_key, &proposal),
+        signer(voter),
+    ]));
+    assert_rejected(
+        try_accounts::<VoteOnProposal>(vec![
+            program_account(vault.key, &vault.account),
+            program_account(other_key, &other_proposal),
+            signer(voter),
+        ]),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn cancel_proposal_rejects_anyone_but_the_proposer() {
+    let vault = vault("builders");
+    let (proposal_key, proposal) = proposal(&vault, Pubkey::new_unique());
+
+    assert_accepted(try_accounts::<CancelProposal>(vec![
+        program_account(proposal_key, &proposal),
+        signer(proposal.proposer),
+    ]));
+    assert_rejected(
+        try_accounts::<CancelProposal>(vec![
+            program_account(proposal_key, &proposal),
+            signer(vault.account.founders[1]),
+        ]),
+        ErrorCode::UnauthorizedProposer,
+    );
+}
+
+#[test]
+fn vote_on_dispute_rejects_dispute_of_another_vault() {
+    let other_vault = vault("rivals");
+    let (other_key, other_dispute) = dispute(&other_vault);
+    let vault = vault("builders");
+    let (dispute_key, dispute) = dispute(&vault);
+    let voter = vault.account.founders[0];
+
+    assert_accepted(try_accounts::<VoteOnDispute>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(dispute_key, &dispute),
+        signer(voter),
+    ]));
+    assert_rejected(
+        try_accounts::<VoteOnDispute>(vec![
+            program_account(vault.key, &vault.account),
+            program_account(other_key, &other_dispute),
+            signer(voter),
+        ]),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn withdraw_from_stream_rejects_anyone_but_the_recipient() {
+    let vault = vault("builders");
+    let recipient = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (stream_key, stream) = payment_stream(&vault, recipient, mint);
+    let thief = Pubkey::new_unique();
+
+    assert_accepted(try_accounts::<WithdrawFromStream>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(stream_key, &stream),
+        token_account(mint, vault.key),
+        token_account(mint, recipient),
+        signer(recipient),
+        token_program(),
+    ]));
+    assert_rejected(
+        try_accounts::<WithdrawFromStream>(vec![
+            program_account(vault.key, &vault.account),
+            program_account(stream_key, &stream),
+            token_account(mint, vault.key),
+            token_account(mint, thief),
+            signer(thief),
+            token_program(),
+        ]),
+        AnchorErrorCode::ConstraintHasOne,
+    );
+}
+
+#[test]
+fn claim_vested_tokens_rejects_anyone_but_the_founder() {
+    let other_vault = vault("others");
+    let vault = vault("builders");
+    let founder = vault.account.founders[0];
+    let mint = Pubkey::new_unique();
+    let (vesting_key, bump) = Pubkey::find_program_address(
+        &[b"founder_vesting", vault.key.as_ref(), founder.as_ref()],
+        &ID,
+    );
+    let vesting = FounderVesting {
+        team_vault: vault.key,
+        founder,
+        beneficiary: founder,
+        mint,
+        total_amount: 1_000,
+        claimed: 0,
+        start_time: 0,
+        cliff_duration: 0,
+        vesting_duration: 1_000,
+        revocable: true,
+        revoked_at: None,
+        bump,
+    };
+    let other_founder = vault.account.founders[1];
+
+    assert_accepted(try_accounts::<ClaimVestedTokens>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(vesting_key, &vesting),
+        token_account(mint, vesting_key),
+        token_account(mint, founder),
+        signer(founder),
+        token_program(),
+    ]));
+    assert_rejected(
+        try_accounts::<ClaimVestedTokens>(vec![
+            program_account(vault.key, &vault.account),
+            program_account(vesting_key, &vesting),
+            token_account(mint, vesting_key),
+            token_account(mint, other_founder),
+            signer(other_founder),
+            token_program(),
+        ]),
+        ErrorCode::UnauthorizedFounder,
+    );
+
+    // After a key rotation the vesting keeps its address but pays the new key
+    let rotated_key = Pubkey::new_unique();
+    let rotated = FounderVesting {
+        beneficiary: rotated_key,
+        ..vesting.clone()
+    };
+    assert_accepted(try_accounts::<ClaimVestedTokens>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(vesting_key, &rotated),
+        token_account(mint, vesting_key),
+        token_account(mint, rotated_key),
+        signer(rotated_key),
+        token_program(),
+    ]));
+    assert_rejected(
+        try_accounts::<ClaimVestedTokens>(vec![
+            program_account(vault.key, &vault.account),
+            program_account(vesting_key, &rotated),
+            token_account(mint, vesting_key),
+            token_account(mint, founder),
+            signer(founder),
+            token_program(),
+        ]),
+        ErrorCode::UnauthorizedFounder,
+    );
+
+    // The vault whose vesting list is updated must be the vesting's own
+    assert_rejected(
+        try_accounts::<ClaimVestedTokens>(vec![
+            program_account(other_vault.key, &other_vault.account),
+            program_account(vesting_key, &vesting),
+            token_account(mint, vesting_key),
+            token_account(mint, founder),
+            signer(founder),
+            token_program(),
+        ]),
+        AnchorErrorCode::ConstraintAddress,
+    );
+}
+
+// =============================================================================
+// SPONSOR ESCROW
+// =============================================================================
+
+#[test]
+fn release_milestone_rejects_anyone_but_the_sponsor() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (escrow_key, escrow) = escrow("quest-1", sponsor, mint);
+    let accounts = |signer_key: Pubkey| {
+        vec![
+            program_account(escrow_key, &escrow),
+            token_account(mint, escrow_key),
+            token_account(mint, escrow.recipients[0]),
+            signer(signer_key),
+            token_program(),
+        ]
+    };
+
+    assert_accepted(try_accounts::<ReleaseMilestone>(accounts(sponsor)));
+    assert_rejected(
+        try_accounts::<ReleaseMilestone>(accounts(Pubkey::new_unique())),
+        ErrorCode::UnauthorizedSponsor,
+    );
+}
+
+#[test]
+fn release_milestone_rejects_token_account_not_owned_by_escrow() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (escrow_key, escrow) = escrow("quest-1", sponsor, mint);
+
+    assert_rejected(
+        try_accounts::<ReleaseMilestone>(vec![
+            program_account(escrow_key, &escrow),
+            token_account(mint, sponsor),
+            token_account(mint, escrow.recipients[0]),
+            signer(sponsor),
+            token_program(),
+        ]),
+        AnchorErrorCode::ConstraintTokenOwner,
+    );
+}
+
+#[test]
+fn release_milestone_rejects_recipient_of_another_mint() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (escrow_key, escrow) = escrow("quest-1", sponsor, mint);
+
+    assert_rejected(
+        try_accounts::<ReleaseMilestone>(vec![
+            program_account(escrow_key, &escrow),
+            token_account(mint, escrow_key),
+            token_account(Pubkey::new_unique(), escrow.recipients[0]),
+            signer(sponsor),
+            token_program(),
+        ]),
+        AnchorErrorCode::ConstraintTokenMint,
+    );
+}
+
+#[test]
+fn release_milestone_rejects_unregistered_recipient() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (escrow_key, escrow) = escrow("quest-1", sponsor, mint);
+
+    assert_rejected(
+        try_accounts::<ReleaseMilestone>(vec![
+            program_account(escrow_key, &escrow),
+            token_account(mint, escrow_key),
+            token_account(mint, sponsor),
+            signer(sponsor),
+            token_program(),
+        ]),
+        ErrorCode::UnregisteredRecipient,
+    );
+}
+
+#[test]
+fn release_milestone_rejects_escrow_outside_its_pda() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (_, escrow) = escrow("quest-1", sponsor, mint);
+    let impostor = Pubkey::new_unique();
+
+    assert_rejected(
+        try_accounts::<ReleaseMilestone>(vec![
+            program_account(impostor, &escrow),
+            token_account(mint, impostor),
+            token_account(mint, escrow.recipients[0]),
+            signer(sponsor),
+            token_program(),
+        ]),
+        AnchorErrorCode::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn reclaim_expired_milestone_rejects_escrow_token_account_of_someone_else() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (escrow_key, escrow) = escrow("quest-1", sponsor, mint);
+
+    assert_rejected(
+        try_accounts::<ReclaimToContributors>(vec![
+            program_account(escrow_key, &escrow),
+            token_account(mint, sponsor),
+            token_program(),
+        ]),
+        AnchorErrorCode::ConstraintTokenOwner,
+    );
+}
+
+// =============================================================================
+// TERRITORY
+// =============================================================================
+
+#[test]
+fn resolve_battle_rejects_anyone_but_the_territory_authority() {
+    let authority = Pubkey::new_unique();
+    let (territory_key, territory) = territory("north", authority);
+    let battle = battle(territory_key);
+    let accounts = |signer_key: Pubkey| {
+        vec![
+            program_account(territory_key, &territory),
+            program_account(Pubkey::new_unique(), &battle),
+            signer(signer_key),
+        ]
+    };
+
+    assert_accepted(try_accounts::<ResolveBattle>(accounts(authority)));
+    assert_rejected(
+        try_accounts::<ResolveBattle>(accounts(battle.challenger)),
+        ErrorCode::UnauthorizedTerritoryAuthority,
+    );
+}
+
+#[test]
+fn resolve_battle_rejects_battle_for_another_territory() {
+    let authority = Pubkey::new_unique();
+    let (other_key, _) = territory("south", authority);
+    let (territory_key, territory) = territory("north", authority);
+
+    assert_rejected(
+        try_accounts::<ResolveBattle>(vec![
+            program_account(territory_key, &territory),
+            program_account(Pubkey::new_unique(), &battle(other_key)),
+            signer(authority),
+        ]),
+        AnchorErrorCode::ConstraintHasOne,
+    );
+}
@@ -0,0 +1,414 @@
+//! Accounts filled to their `#[max_len]` bounds must serialize into exactly
+//! the space reserved for them, and proposals past those bounds are refused.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, Space};
+use common::team_vault;
+use rise_of_founders_protocol::{
+    AgreementSignature, AgreementStatus, Battle, BattleStatus, BattleType, Contribution, Dispute,
+    DisputeStatus, DisputeType, ErrorCode, EscrowStatus, FounderAgreement, GovernanceConfig,
+    Milestone, MilestoneDistribution, MilestoneShare, Payout, Prize, PrizeTable, Proposal,
+    ProposalStatus, ProposalType, ResultVote, SpendingAllowance, SponsorEscrow, TeamVault,
+    Territory, VaultAccountMeta, VaultBalance, VaultFreeze, VaultInstruction, Vote, VoteDelegation,
+    DISTRIBUTION_BITMAP_LEN, MAX_ALLOWANCES, MAX_ALLOWED_PROGRAMS, MAX_CONTRIBUTORS,
+    MAX_DESCRIPTION_LEN, MAX_DISTRIBUTION_CLAIMANTS, MAX_FOUNDERS, MAX_ID_LEN,
+    MAX_INSTRUCTION_ACCOUNTS, MAX_INSTRUCTION_DATA_LEN, MAX_JUDGES, MAX_MILESTONES,
+    MAX_MILESTONE_TITLE_LEN, MAX_PAYOUTS, MAX_PRIZE_RANKS, MAX_RECIPIENTS, MAX_TEAM_NAME_LEN,
+    MAX_TITLE_LEN, MAX_URI_LEN, MAX_VAULT_INSTRUCTIONS, MAX_VAULT_MINTS,
+};
+
+fn keys(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+fn text(len: usize) -> String {
+    "x".repeat(len)
+}
+
+fn assert_fills_space<T: AccountSerialize + Space>(account: &T) {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), 8 + T::INIT_SPACE);
+}
+
+fn max_team_vault() -> TeamVault {
+    let founders = keys(MAX_FOUNDERS);
+    TeamVault {
+        team_id: text(MAX_ID_LEN),
+        name: text(MAX_TEAM_NAME_LEN),
+        founder_weights: Some(vec![1_000; MAX_FOUNDERS]),
+        threshold: 60,
+        governance: GovernanceConfig {
+            voting_period: 86_400,
+            execution_delay: 3_600,
+            quorum: Some(MAX_FOUNDERS as u8),
+        },
+        allowed_programs: keys(MAX_ALLOWED_PROGRAMS),
+        allowances: (0..MAX_ALLOWANCES)
+            .map(|i| SpendingAllowance {
+                founder: founders[i % MAX_FOUNDERS],
+                mint: Pubkey::new_unique(),
+                amount_per_period: 1_000,
+                period: 86_400,
+                spent: 0,
+                period_start: 0,
+            })
+            .collect(),
+        delegations: founders
+            .iter()
+            .map(|founder| VoteDelegation {
+                founder: *founder,
+                delegate: Pubkey::new_unique(),
+                expires_at: i64::MAX,
+            })
+            .collect(),
+        vesting_founders: founders.clone(),
+        founders,
+        arbitrator: Some(Pubkey::new_unique()),
+        freezes: (0..MAX_FOUNDERS)
+            .map(|_| VaultFreeze {
+                initiator: Pubkey::new_unique(),
+                until: i64::MAX,
+            })
+            .collect(),
+        agreement_version: 1,
+        agreement_required: true,
+        agreement_current: true,
+        balances: (0..MAX_VAULT_MINTS)
+            .map(|_| VaultBalance {
+                mint: Pubkey::new_unique(),
+                amount: u64::MAX,
+            })
+            .collect(),
+        closed: true,
+        ..team_vault("")
+    }
+}
+
+fn max_votes() -> Vec<Vote> {
+    (0..MAX_FOUNDERS)
+        .map(|_| Vote {
+            voter: Pubkey::new_unique(),
+            delegator: Some(Pubkey::new_unique()),
+            support: true,
+            timestamp: 0,
+        })
+        .collect()
+}
+
+fn max_instructions() -> Vec<VaultInstruction> {
+    (0..MAX_VAULT_INSTRUCTIONS)
+        .map(|_| VaultInstruction {
+            program_id: Pubkey::new_unique(),
+            accounts: (0..MAX_INSTRUCTION_ACCOUNTS)
+                .map(|_| VaultAccountMeta {
+                    pubkey: Pubkey::new_unique(),
+                    is_signer: false,
+                    is_writable: true,
+                })
+                .collect(),
+            data: vec![0; MAX_INSTRUCTION_DATA_LEN],
+        })
+        .collect()
+}
+
+#[test]
+fn team_vault_at_max_size_fits() {
+    assert_fills_space(&max_team_vault());
+}
+
+#[test]
+fn proposal_at_max_size_fits() {
+    let proposal = Proposal {
+        team_vault: Pubkey::new_unique(),
+        proposer: Pubkey::new_unique(),
+        title: text(MAX_TITLE_LEN),
+        description: text(MAX_DESCRIPTION_LEN),
+        recipient: Pubkey::new_unique(),
+        amount: u64::MAX,
+        mint: Some(Pubkey::new_unique()),
+        proposal_type: ProposalType::ExecuteInstructions {
+            instructions: max_instructions(),
+        },
+        votes: max_votes(),
+        status: ProposalStatus::PartiallyExecuted,
+        config_version: 0,
+        created_at: 0,
+        expires_at: 0,
+        approved_at: Some(0),
+        executed_at: Some(0),
+        executed_payouts: 0,
+        skipped_payouts: 0,
+        index: u64::MAX,
+        bump: 255,
+    };
+
+    assert_fills_space(&proposal);
+}
+
+#[test]
+fn founder_agreement_at_max_size_fits() {
+    let founders = keys(MAX_FOUNDERS);
+    let agreement = FounderAgreement {
+        team_id: text(MAX_ID_LEN),
+        creator: Pubkey::new_unique(),
+        version: 1,
+        document_hash: [7; 32],
+        signatures: founders
+            .iter()
+            .map(|founder| AgreementSignature {
+                founder: *founder,
+                signed_at: 0,
+            })
+            .collect(),
+        founders,
+        status: AgreementStatus::FullySigned,
+        created_at: 0,
+        fully_signed_at: Some(0),
+        bump: 255,
+    };
+
+    assert_fills_space(&agreement);
+}
+
+#[test]
+fn dispute_at_max_size_fits() {
+    let dispute = Dispute {
+        team_vault: Pubkey::new_unique(),
+        index: 0,
+        initiator: Pubkey::new_unique(),
+        dispute_type: DisputeType::TeamSeparation,
+        evidence_hash: [7; 32],
+        freezes_vault: true,
+        arbitrator: Pubkey::new_unique(),
+        votes: max_votes(),
+        status: DisputeStatus::Open,
+        resolved_by_arbitrator: false,
+        created_at: 0,
+        voting_deadline: 0,
+        resolved_at: Some(0),
+        bump: 255,
+    };
+
+    assert_fills_space(&dispute);
+}
+
+#[test]
+fn sponsor_escrow_at_max_size_fits() {
+    let escrow = SponsorEscrow {
+        quest_id: text(MAX_ID_LEN),
+        sponsor: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        total_amount: u64::MAX,
+        released_amount: 0,
+        refunded_amount: 0,
+        reserved_amount: 0,
+        contributions: keys(MAX_CONTRIBUTORS)
+            .into_iter()
+            .map(|contributor| Contribution {
+                contributor,
+                amount: u64::MAX,
+            })
+            .collect(),
+        min_contribution: u64::MAX,
+        owed_refunds: keys(MAX_CONTRIBUTORS)
+            .into_iter()
+            .map(|contributor| Contribution {
+                contributor,
+                amount: u64::MAX,
+            })
+            .collect(),
+        milestones: (0..MAX_MILESTONES)
+            .map(|_| Milestone {
+                title: text(MAX_MILESTONE_TITLE_LEN),
+                description: text(MAX_DESCRIPTION_LEN),
+                share: MilestoneShare::Amount(u64::MAX),
+                released: true,
+                released_at: Some(0),
+                deadline: Some(0),
+                refunded: false,
+                settled_amount: u64::MAX,
+                approvals: keys(MAX_JUDGES),
+            })
+            .collect(),
+        judges: keys(MAX_JUDGES),
+        judge_threshold: MAX_JUDGES as u8,
+        recipients: keys(MAX_RECIPIENTS),
+        cancel_approvals: keys(MAX_JUDGES),
+        prize_table: PrizeTable {
+            pool: u64::MAX,
+            rank_bps: vec![1_000; MAX_PRIZE_RANKS],
+            claim_window: 86_400,
+            results_deadline: i64::MAX,
+        },
+        result_votes: keys(MAX_JUDGES)
+            .into_iter()
+            .map(|judge| ResultVote {
+                judge,
+                winners: keys(MAX_PRIZE_RANKS),
+            })
+            .collect(),
+        prizes: keys(MAX_PRIZE_RANKS)
+            .into_iter()
+            .map(|winner| Prize {
+                winner,
+                amount: u64::MAX,
+                claimed_at: Some(0),
+            })
+            .collect(),
+        results_finalized_at: Some(0),
+        prizes_settled: false,
+        status: EscrowStatus::Active,
+        bump: 255,
+        created_at: 0,
+    };
+
+    assert_fills_space(&escrow);
+}
+
+#[test]
+fn milestone_distribution_at_max_size_fits() {
+    let distribution = MilestoneDistribution {
+        escrow: Pubkey::new_unique(),
+        milestone_index: 0,
+        merkle_root: [7; 32],
+        total_amount: u64::MAX,
+        claimed_amount: 0,
+        claimant_count: MAX_DISTRIBUTION_CLAIMANTS,
+        expires_at: 0,
+        claimed_bitmap: vec![0; DISTRIBUTION_BITMAP_LEN],
+        bump: 255,
+    };
+
+    assert_fills_space(&distribution);
+}
+
+#[test]
+fn territory_and_battle_at_max_size_fit() {
+    let territory = Territory {
+        territory_id: text(MAX_ID_LEN),
+        name: text(MAX_TITLE_LEN),
+        description: text(MAX_DESCRIPTION_LEN),
+        coordinates: [i32::MIN, i32::MAX],
+        size: u32::MAX,
+        difficulty: 5,
+        max_teams: u16::MAX,
+        current_teams: 0,
+        uri: text(MAX_URI_LEN),
+        owner: Some(Pubkey::new_unique()),
+        authority: Pubkey::new_unique(),
+        battles_won: 0,
+        battles_lost: 0,
+        total_rewards: 0,
+        is_active: true,
+        bump: 255,
+        created_at: 0,
+    };
+    let battle = Battle {
+        territory: Pubkey::new_unique(),
+        challenger: Pubkey::new_unique(),
+        challenger_team_id: text(MAX_ID_LEN),
+        defender: Some(Pubkey::new_unique()),
+        battle_type: BattleType::Conquest,
+        status: BattleStatus::Completed,
+        stake_amount: 0,
+        winner: Some(Pubkey::new_unique()),
+        score: 0,
+        created_at: 0,
+        expires_at: 0,
+        resolved_at: Some(0),
+    };
+
+    assert_fills_space(&territory);
+    assert_fills_space(&battle);
+}
+
+#[test]
+fn proposals_past_bounds_are_rejected() {
+    let mut team_vault = max_team_vault();
+    team_vault.allowed_programs = max_instructions()
+        .iter()
+        .map(|instruction| instruction.program_id)
+        .collect();
+
+    let mut instructions = max_instructions();
+    for instruction in instructions.iter_mut() {
+        instruction.program_id = team_vault.allowed_programs[0];
+    }
+    assert!(team_vault
+        .validate_proposal_type(&ProposalType::ExecuteInstructions {
+            instructions: instructions.clone(),
+        })
+        .is_ok());
+
+    let mut too_much_data = instructions.clone();
+    too_much_data[0].data.push(0);
+    assert_eq!(
+        team_vault
+            .validate_proposal_type(&ProposalType::ExecuteInstructions {
+                instructions: too_much_data
+            })
+            .unwrap_err(),
+        ErrorCode::InvalidVaultInstructions.into()
+    );
+
+    let mut too_many_instructions = instructions;
+    too_many_instructions.push(too_many_instructions[0].clone());
+    assert_eq!(
+        team_vault
+            .validate_proposal_type(&ProposalType::ExecuteInstructions {
+                instructions: too_many_instructions,
+            })
+            .unwrap_err(),
+        ErrorCode::InvalidVaultInstructions.into()
+    );
+
+    assert!(team_vault
+        .validate_proposal_type(&ProposalType::RenameTeam {
+            name: text(MAX_TEAM_NAME_LEN)
+        })
+        .is_ok());
+    assert_eq!(
+        team_vault
+            .validate_proposal_type(&ProposalType::RenameTeam {
+                name: text(MAX_TEAM_NAME_LEN + 1)
+            })
+            .unwrap_err(),
+        ErrorCode::InvalidTeamName.into()
+    );
+
+    let payouts = vec![
+        Payout {
+            recipient: Pubkey::new_unique(),
+            amount: 1,
+        };
+        MAX_PAYOUTS + 1
+    ];
+    assert_eq!(
+        team_vault
+            .validate_proposal_type(&ProposalType::BatchTransfer { payouts })
+            .unwrap_err(),
+        ErrorCode::InvalidPayouts.into()
+    );
+
+    assert_eq!(
+        team_vault
+            .validate_proposal_type(&ProposalType::SetAllowedPrograms {
+                programs: keys(MAX_ALLOWED_PROGRAMS + 1),
+            })
+            .unwrap_err(),
+        ErrorCode::TooManyAllowedPrograms.into()
+    );
+
+    assert_eq!(
+        team_vault
+            .validate_proposal_type(&ProposalType::AddFounder {
+                founder: Pubkey::new_unique(),
+                weight_bps: 0,
+            })
+            .unwrap_err(),
+        ErrorCode::TooManyFounders.into()
+    );
+}
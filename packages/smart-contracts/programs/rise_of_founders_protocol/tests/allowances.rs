@@ -0,0 +1,102 @@
+//! Spending allowances reset on period boundaries aligned to when they were
+//! granted, and stop while a dispute freezes the vault.
+
+mod common;
+
+use anchor_lang::prelude::{Context, Pubkey};
+use common::{
+    assert_rejected, program_account, signer, token_account, token_program, try_accounts, vault,
+};
+use rise_of_founders_protocol::rise_of_founders_protocol::spend_from_allowance;
+use rise_of_founders_protocol::{
+    ErrorCode, SpendFromAllowance, SpendingAllowance, VaultFreeze, ID,
+};
+
+fn allowance(period_start: i64) -> SpendingAllowance {
+    SpendingAllowance {
+        founder: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        amount_per_period: 100,
+        period: 1_000,
+        spent: 0,
+        period_start,
+    }
+}
+
+#[test]
+fn spending_is_capped_within_a_period() {
+    let mut allowance = allowance(0);
+
+    allowance.debit(60, 0).unwrap();
+    allowance.debit(40, 999).unwrap();
+    assert_eq!(
+        allowance.debit(1, 999).unwrap_err(),
+        ErrorCode::AllowanceExceeded.into()
+    );
+    assert_eq!(allowance.spent, 100);
+}
+
+#[test]
+fn a_new_period_starts_exactly_at_the_boundary() {
+    let mut allowance = allowance(0);
+    allowance.debit(100, 0).unwrap();
+
+    allowance.debit(100, 1_000).unwrap();
+    assert_eq!(allowance.period_start, 1_000);
+    assert_eq!(allowance.spent, 100);
+}
+
+#[test]
+fn idle_periods_keep_the_schedule_aligned() {
+    let mut allowance = allowance(500);
+    allowance.debit(100, 500).unwrap();
+
+    // Several periods later the new one starts on the original grid, not at
+    // the time of the spend
+    allowance.debit(30, 3_700).unwrap();
+    assert_eq!(allowance.period_start, 3_500);
+    assert_eq!(allowance.spent, 30);
+
+    allowance.debit(70, 4_499).unwrap();
+    assert_eq!(
+        allowance.debit(1, 4_499).unwrap_err(),
+        ErrorCode::AllowanceExceeded.into()
+    );
+    allowance.debit(1, 4_500).unwrap();
+}
+
+#[test]
+fn frozen_vaults_refuse_allowance_spending() {
+    let mut vault = vault("builders");
+    let founder = vault.account.founders[0];
+    let mint = vault.account.balances[0].mint;
+    vault.account.allowances = vec![SpendingAllowance {
+        founder,
+        mint,
+        ..allowance(0)
+    }];
+    let spend = |freezes: Vec<VaultFreeze>| {
+        let mut vault_account = vault.account.clone();
+        vault_account.freezes = freezes;
+        let mut accounts = try_accounts::<SpendFromAllowance>(vec![
+            program_account(vault.key, &vault_account),
+            token_account(mint, vault.key),
+            token_account(mint, Pubkey::new_unique()),
+            signer(founder),
+            token_program(),
+        ])
+        .unwrap();
+        spend_from_allowance(
+            Context::new(&ID, &mut accounts, &[], Default::default()),
+            10,
+        )
+    };
+
+    let freeze = |until| VaultFreeze {
+        initiator: vault.account.founders[1],
+        until,
+    };
+    assert_rejected(spend(vec![freeze(1)]), ErrorCode::VaultFrozenByDispute);
+    // A lapsed freeze no longer applies
+    spend(vec![freeze(0)]).unwrap();
+}
@@ -0,0 +1,113 @@
+//! A batch payout skips a recipient only when their associated token account
+//! cannot take the transfer, and a skipped payout stays owed until it is paid.
+
+mod common;
+
+use anchor_lang::prelude::{Context, Pubkey};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token::state::AccountState;
+use common::{
+    account_info, assert_rejected, program_account, proposal, token_account, token_account_at,
+    token_program, try_accounts, vault,
+};
+use rise_of_founders_protocol::rise_of_founders_protocol::pay_skipped_payout;
+use rise_of_founders_protocol::{
+    is_unpayable_recipient, ErrorCode, PaySkippedPayout, Payout, ProposalStatus, ProposalType, ID,
+};
+
+#[test]
+fn batch_transfer_skips_only_unpayable_associated_accounts() {
+    let (recipient, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let associated = get_associated_token_address(&recipient, &mint);
+
+    let open = token_account_at(associated, mint, recipient, AccountState::Initialized);
+    assert!(!is_unpayable_recipient(&open, &recipient, &mint));
+
+    let frozen = token_account_at(associated, mint, recipient, AccountState::Frozen);
+    assert!(is_unpayable_recipient(&frozen, &recipient, &mint));
+
+    let missing = account_info(associated, Pubkey::default(), Vec::new(), false, false);
+    assert!(is_unpayable_recipient(&missing, &recipient, &mint));
+
+    // Anything but the recipient's associated account goes through the usual
+    // checks, so an executor cannot skip a payout by passing a bogus account
+    let bogus = account_info(
+        Pubkey::new_unique(),
+        Pubkey::default(),
+        Vec::new(),
+        false,
+        false,
+    );
+    assert!(!is_unpayable_recipient(&bogus, &recipient, &mint));
+}
+
+#[test]
+fn skipped_payouts_can_be_paid_once_later() {
+    let vault = vault("builders");
+    let mint = vault.account.balances[0].mint;
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let (proposal_key, mut proposal) = proposal(&vault, Pubkey::new_unique());
+    proposal.mint = Some(mint);
+    proposal.proposal_type = ProposalType::BatchTransfer {
+        payouts: recipients
+            .iter()
+            .map(|recipient| Payout {
+                recipient: *recipient,
+                amount: 100,
+            })
+            .collect(),
+    };
+    proposal.status = ProposalStatus::Executed;
+    proposal.executed_payouts = 2;
+    proposal.skipped_payouts = 0b10;
+
+    let mut accounts = try_accounts::<PaySkippedPayout>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(proposal_key, &proposal),
+        token_account(mint, vault.key),
+        token_account(mint, recipients[1]),
+        token_program(),
+    ])
+    .unwrap();
+    let mut pay = |payout_index| {
+        pay_skipped_payout(
+            Context::new(&ID, &mut accounts, &[], Default::default()),
+            payout_index,
+        )
+    };
+
+    assert_rejected(pay(0), ErrorCode::PayoutNotSkipped);
+    pay(1).unwrap();
+    assert_rejected(pay(1), ErrorCode::PayoutNotSkipped);
+    assert_eq!(accounts.proposal.skipped_payouts, 0);
+    assert_eq!(accounts.team_vault.balance_of(&mint), 1_000_000 - 100);
+}
+
+#[test]
+fn skipped_payouts_go_only_to_their_recipient() {
+    let vault = vault("builders");
+    let mint = vault.account.balances[0].mint;
+    let (proposal_key, mut proposal) = proposal(&vault, Pubkey::new_unique());
+    proposal.mint = Some(mint);
+    proposal.proposal_type = ProposalType::BatchTransfer {
+        payouts: vec![Payout {
+            recipient: Pubkey::new_unique(),
+            amount: 100,
+        }],
+    };
+    proposal.skipped_payouts = 0b1;
+
+    let mut accounts = try_accounts::<PaySkippedPayout>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(proposal_key, &proposal),
+        token_account(mint, vault.key),
+        token_account(mint, Pubkey::new_unique()),
+        token_program(),
+    ])
+    .unwrap();
+
+    assert_rejected(
+        pay_skipped_payout(Context::new(&ID, &mut accounts, &[], Default::default()), 0),
+        ErrorCode::InvalidRecipientTokenAccount,
+    );
+}
@@ -0,0 +1,407 @@
+//! Harness and fixtures shared by the integration tests. Accounts are leaked
+//! so contexts built from them can borrow for `'static`.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeSet;
+use std::sync::Once;
+
+use anchor_lang::prelude::{AccountInfo, Clock, Pubkey};
+use anchor_lang::solana_program::entrypoint::SUCCESS;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::rent::Rent;
+use anchor_lang::{AccountSerialize, Accounts, Bumps};
+use anchor_spl::token::spl_token;
+use rise_of_founders_protocol::{
+    Battle, BattleStatus, BattleType, Contribution, Dispute, DisputeStatus, DisputeType,
+    EscrowStatus, GovernanceConfig, Milestone, MilestoneShare, PaymentStream, PrizeTable, Proposal,
+    ProposalStatus, ProposalType, SponsorEscrow, TeamVault, Territory, VaultBalance, ID,
+};
+
+// =============================================================================
+// HARNESS
+// =============================================================================
+
+pub fn account_info(
+    key: Pubkey,
+    owner: Pubkey,
+    data: Vec<u8>,
+    is_signer: bool,
+    executable: bool,
+) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        is_signer,
+        true,
+        Box::leak(Box::new(1_000_000_000)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        executable,
+        0,
+    )
+}
+
+pub fn program_account<T: AccountSerialize>(key: Pubkey, account: &T) -> AccountInfo<'static> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    account_info(key, ID, data, false, false)
+}
+
+/// Like `program_account`, but laid out the way the runtime serializes
+/// accounts, with the original and current data lengths ahead of the key and
+/// the data, so a handler can `realloc` it
+pub fn resizable_program_account<T: AccountSerialize>(
+    key: Pubkey,
+    account: &T,
+) -> AccountInfo<'static> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+
+    let mut key_buffer = (data.len() as u32).to_le_bytes().to_vec();
+    key_buffer.extend_from_slice(key.as_ref());
+    let key_buffer = Box::leak(key_buffer.into_boxed_slice());
+    let key = unsafe { &*(key_buffer[4..].as_ptr() as *const Pubkey) };
+
+    let mut data_buffer = (data.len() as u64).to_le_bytes().to_vec();
+    data_buffer.extend_from_slice(&data);
+    let data_buffer = Box::leak(data_buffer.into_boxed_slice());
+
+    AccountInfo::new(
+        key,
+        false,
+        true,
+        Box::leak(Box::new(1_000_000_000)),
+        &mut data_buffer[8..],
+        Box::leak(Box::new(ID)),
+        false,
+        0,
+    )
+}
+
+pub fn token_account(mint: Pubkey, authority: Pubkey) -> AccountInfo<'static> {
+    token_account_at(
+        Pubkey::new_unique(),
+        mint,
+        authority,
+        spl_token::state::AccountState::Initialized,
+    )
+}
+
+pub fn token_account_at(
+    key: Pubkey,
+    mint: Pubkey,
+    authority: Pubkey,
+    state: spl_token::state::AccountState,
+) -> AccountInfo<'static> {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner: authority,
+        amount: 1_000_000,
+        delegate: COption::None,
+        state,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    account_info(key, spl_token::ID, data, false, false)
+}
+
+pub fn signer(key: Pubkey) -> AccountInfo<'static> {
+    account_info(key, Pubkey::default(), Vec::new(), true, false)
+}
+
+pub fn token_program() -> AccountInfo<'static> {
+    account_info(spl_token::ID, Pubkey::default(), Vec::new(), false, true)
+}
+
+/// Placeholder for an optional account that is not passed
+pub fn none() -> AccountInfo<'static> {
+    account_info(ID, Pubkey::default(), Vec::new(), false, false)
+}
+
+/// Serves the rent sysvar that contexts with `init` accounts read up front,
+/// and a clock at the epoch for handlers run against a context
+pub struct SysvarStub;
+
+impl SyscallStubs for SysvarStub {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { (var_addr as *mut Rent).write_unaligned(Rent::default()) };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { (var_addr as *mut Clock).write_unaligned(Clock::default()) };
+        SUCCESS
+    }
+}
+
+pub fn try_accounts<T>(infos: Vec<AccountInfo<'static>>) -> anchor_lang::Result<T>
+where
+    T: Accounts<'static, <T as Bumps>::Bumps> + Bumps,
+    <T as Bumps>::Bumps: Default,
+{
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(SysvarStub));
+    });
+
+    let mut infos: &'static [AccountInfo<'static>] = Box::leak(infos.into_boxed_slice());
+    T::try_accounts(
+        &ID,
+        &mut infos,
+        &[],
+        &mut Default::default(),
+        &mut BTreeSet::new(),
+    )
+}
+
+pub fn assert_accepted<T>(result: anchor_lang::Result<T>) {
+    if let Err(error) = result {
+        panic!("genuine accounts were rejected: {error:?}");
+    }
+}
+
+pub fn assert_rejected<T>(
+    result: anchor_lang::Result<T>,
+    expected: impl Into<anchor_lang::error::Error>,
+) {
+    match result {
+        Ok(_) => panic!("substituted account was accepted"),
+        Err(error) => assert_eq!(error, expected.into()),
+    }
+}
+
+// =============================================================================
+// FIXTURES
+// =============================================================================
+
+pub struct Vault {
+    pub key: Pubkey,
+    pub account: TeamVault,
+}
+
+/// A two-founder vault holding nothing, outside any PDA
+pub fn team_vault(team_id: &str) -> TeamVault {
+    TeamVault {
+        team_id: team_id.to_string(),
+        name: team_id.to_string(),
+        founders: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        founder_weights: None,
+        threshold: 2,
+        governance: GovernanceConfig {
+            voting_period: 86_400,
+            execution_delay: 0,
+            quorum: None,
+        },
+        allowed_programs: Vec::new(),
+        allowances: Vec::new(),
+        delegations: Vec::new(),
+        vesting_founders: Vec::new(),
+        arbitrator: None,
+        dispute_count: 0,
+        open_disputes: 0,
+        freezes: Vec::new(),
+        agreement_version: 0,
+        agreement_required: false,
+        agreement_current: false,
+        balances: Vec::new(),
+        proposal_count: 0,
+        config_version: 0,
+        bump: 255,
+        created_at: 0,
+        is_active: true,
+        closed: false,
+    }
+}
+
+/// A funded vault at its PDA with one proposal and one dispute opened
+pub fn vault(team_id: &str) -> Vault {
+    let (key, bump) = Pubkey::find_program_address(&[b"team_vault", team_id.as_bytes()], &ID);
+    let account = TeamVault {
+        dispute_count: 1,
+        balances: vec![VaultBalance {
+            mint: Pubkey::new_unique(),
+            amount: 1_000_000,
+        }],
+        proposal_count: 1,
+        bump,
+        ..team_vault(team_id)
+    };
+
+    Vault { key, account }
+}
+
+pub fn proposal(vault: &Vault, recipient: Pubkey) -> (Pubkey, Proposal) {
+    let index = 0u64;
+    let (key, bump) = Pubkey::find_program_address(
+        &[b"proposal", vault.key.as_ref(), &index.to_le_bytes()],
+        &ID,
+    );
+    let account = Proposal {
+        team_vault: vault.key,
+        proposer: vault.account.founders[0],
+        title: "Pay contractor".to_string(),
+        description: String::new(),
+        recipient,
+        amount: 100,
+        mint: Some(Pubkey::new_unique()),
+        proposal_type: ProposalType::Transfer,
+        votes: Vec::new(),
+        status: ProposalStatus::Approved,
+        config_version: 0,
+        created_at: 0,
+        expires_at: i64::MAX,
+        approved_at: Some(0),
+        executed_at: None,
+        executed_payouts: 0,
+        skipped_payouts: 0,
+        index,
+        bump,
+    };
+
+    (key, account)
+}
+
+pub fn payment_stream(vault: &Vault, recipient: Pubkey, mint: Pubkey) -> (Pubkey, PaymentStream) {
+    let proposal = Pubkey::new_unique();
+    let (key, bump) = Pubkey::find_program_address(&[b"payment_stream", proposal.as_ref()], &ID);
+    let account = PaymentStream {
+        team_vault: vault.key,
+        proposal,
+        recipient,
+        mint,
+        rate_per_second: 1,
+        start_time: 0,
+        cliff_time: 0,
+        end_time: 1_000,
+        withdrawn: 0,
+        cancelled_at: None,
+        bump,
+    };
+
+    (key, account)
+}
+
+pub fn dispute(vault: &Vault) -> (Pubkey, Dispute) {
+    let index = 0u64;
+    let (key, bump) =
+        Pubkey::find_program_address(&[b"dispute", vault.key.as_ref(), &index.to_le_bytes()], &ID);
+    let account = Dispute {
+        team_vault: vault.key,
+        index,
+        initiator: vault.account.founders[0],
+        dispute_type: DisputeType::DecisionMaking,
+        evidence_hash: [0; 32],
+        freezes_vault: false,
+        arbitrator: Pubkey::new_unique(),
+        votes: Vec::new(),
+        status: DisputeStatus::Open,
+        resolved_by_arbitrator: false,
+        created_at: 0,
+        voting_deadline: i64::MAX,
+        resolved_at: None,
+        bump,
+    };
+
+    (key, account)
+}
+
+pub fn escrow(quest_id: &str, sponsor: Pubkey, mint: Pubkey) -> (Pubkey, SponsorEscrow) {
+    let (key, bump) = Pubkey::find_program_address(&[b"sponsor_escrow", quest_id.as_bytes()], &ID);
+    let account = SponsorEscrow {
+        quest_id: quest_id.to_string(),
+        sponsor,
+        mint,
+        total_amount: 1_000,
+        released_amount: 0,
+        refunded_amount: 0,
+        reserved_amount: 0,
+        contributions: vec![Contribution {
+            contributor: sponsor,
+            amount: 1_000,
+        }],
+        min_contribution: 0,
+        owed_refunds: Vec::new(),
+        milestones: Vec::new(),
+        judges: Vec::new(),
+        judge_threshold: 0,
+        recipients: vec![Pubkey::new_unique()],
+        cancel_approvals: Vec::new(),
+        prize_table: PrizeTable {
+            pool: 0,
+            rank_bps: Vec::new(),
+            claim_window: 0,
+            results_deadline: 0,
+        },
+        result_votes: Vec::new(),
+        prizes: Vec::new(),
+        results_finalized_at: None,
+        prizes_settled: true,
+        status: EscrowStatus::Active,
+        bump,
+        created_at: 0,
+    };
+
+    (key, account)
+}
+
+/// An unsettled, unapproved milestone whose deadline has passed
+pub fn milestone(share: MilestoneShare) -> Milestone {
+    Milestone {
+        title: String::new(),
+        description: String::new(),
+        share,
+        released: false,
+        released_at: None,
+        deadline: Some(0),
+        refunded: false,
+        settled_amount: 0,
+        approvals: Vec::new(),
+    }
+}
+
+pub fn territory(territory_id: &str, authority: Pubkey) -> (Pubkey, Territory) {
+    let (key, bump) = Pubkey::find_program_address(&[b"territory", territory_id.as_bytes()], &ID);
+    let account = Territory {
+        territory_id: territory_id.to_string(),
+        name: territory_id.to_string(),
+        description: String::new(),
+        coordinates: [0, 0],
+        size: 1,
+        difficulty: 1,
+        max_teams: 2,
+        current_teams: 0,
+        uri: String::new(),
+        owner: None,
+        authority,
+        battles_won: 0,
+        battles_lost: 0,
+        total_rewards: 0,
+        is_active: true,
+        bump,
+        created_at: 0,
+    };
+
+    (key, account)
+}
+
+pub fn battle(territory: Pubkey) -> Battle {
+    Battle {
+        territory,
+        challenger: Pubkey::new_unique(),
+        challenger_team_id: "challengers".to_string(),
+        defender: None,
+        battle_type: BattleType::Conquest,
+        status: BattleStatus::Pending,
+        stake_amount: 0,
+        winner: None,
+        score: 0,
+        created_at: 0,
+        expires_at: i64::MAX,
+        resolved_at: None,
+    }
+}
@@ -0,0 +1,156 @@
+//! A founder's dispute freeze holds until its voting deadline even if they
+//! cancel, and each founder must let a voting period pass before freezing
+//! the vault again.
+
+mod common;
+
+use anchor_lang::prelude::{Account, Context, Pubkey};
+use common::{assert_rejected, dispute, program_account, signer, try_accounts, vault};
+use rise_of_founders_protocol::rise_of_founders_protocol::cancel_dispute;
+use rise_of_founders_protocol::{
+    move_founder_records, CancelDispute, Dispute, DisputeStatus, ErrorCode, TeamVault, ID,
+};
+
+/// Voting period of the fixture vault, which doubles as the freeze cooldown
+const PERIOD: i64 = 86_400;
+
+fn team_vault() -> TeamVault {
+    vault("builders").account
+}
+
+#[test]
+fn founders_hold_one_freeze_at_a_time() {
+    let mut vault = team_vault();
+    let (alice, bob) = (vault.founders[0], vault.founders[1]);
+
+    vault.freeze(alice, PERIOD, 0).unwrap();
+    vault.freeze(bob, PERIOD, 0).unwrap();
+    assert!(vault.is_frozen(PERIOD - 1));
+    assert!(!vault.is_frozen(PERIOD));
+
+    assert_eq!(
+        vault.freeze(alice, 2 * PERIOD, 10).unwrap_err(),
+        ErrorCode::FreezeAlreadyOpen.into()
+    );
+}
+
+#[test]
+fn freezes_cool_down_for_a_voting_period() {
+    let mut vault = team_vault();
+    let alice = vault.founders[0];
+    vault.freeze(alice, PERIOD, 0).unwrap();
+
+    assert_eq!(
+        vault.freeze(alice, 3 * PERIOD, 2 * PERIOD - 1).unwrap_err(),
+        ErrorCode::FreezeCoolingDown.into()
+    );
+    vault.freeze(alice, 3 * PERIOD, 2 * PERIOD).unwrap();
+    assert_eq!(vault.freezes.len(), 1);
+    assert!(vault.is_frozen(3 * PERIOD - 1));
+}
+
+#[test]
+fn decided_disputes_lift_the_freeze_at_once() {
+    let mut vault = vault("builders");
+    let (_, mut dispute) = dispute(&vault);
+    dispute.freezes_vault = true;
+    dispute.voting_deadline = PERIOD;
+    vault
+        .account
+        .freeze(dispute.initiator, dispute.voting_deadline, 0)
+        .unwrap();
+    vault.account.open_disputes = 1;
+
+    dispute.resolve(&mut vault.account, DisputeStatus::Rejected, false, 100);
+    assert!(!vault.account.is_frozen(100));
+    assert_eq!(vault.account.open_disputes, 0);
+
+    // The cooldown runs from when the freeze was lifted
+    assert_eq!(
+        vault
+            .account
+            .freeze(dispute.initiator, PERIOD, 100 + PERIOD - 1)
+            .unwrap_err(),
+        ErrorCode::FreezeCoolingDown.into()
+    );
+    vault
+        .account
+        .freeze(dispute.initiator, 2 * PERIOD, 100 + PERIOD)
+        .unwrap();
+}
+
+#[test]
+fn cancelling_keeps_the_freeze_to_its_deadline() {
+    let mut vault = vault("builders");
+    let (dispute_key, mut dispute) = dispute(&vault);
+    dispute.freezes_vault = true;
+    dispute.voting_deadline = PERIOD;
+    vault
+        .account
+        .freeze(dispute.initiator, dispute.voting_deadline, 0)
+        .unwrap();
+    vault.account.open_disputes = 1;
+
+    let cancel = |signer_key: Pubkey| {
+        let mut accounts = try_accounts::<CancelDispute>(vec![
+            program_account(vault.key, &vault.account),
+            program_account(dispute_key, &dispute),
+            signer(signer_key),
+        ])
+        .unwrap();
+        cancel_dispute(Context::new(&ID, &mut accounts, &[], Default::default())).map(|_| accounts)
+    };
+
+    assert_rejected(
+        cancel(vault.account.founders[1]),
+        ErrorCode::UnauthorizedFounder,
+    );
+
+    let accounts = cancel(dispute.initiator).unwrap();
+    assert!(accounts.dispute.status == DisputeStatus::Cancelled);
+    assert_eq!(accounts.team_vault.open_disputes, 0);
+    assert!(accounts.team_vault.is_frozen(PERIOD - 1));
+    assert!(!accounts.team_vault.is_frozen(PERIOD));
+
+    // Reopening straight away cannot extend the freeze
+    let mut team_vault = accounts.team_vault.clone().into_inner();
+    assert_eq!(
+        team_vault
+            .freeze(dispute.initiator, 2 * PERIOD, 0)
+            .unwrap_err(),
+        ErrorCode::FreezeAlreadyOpen.into()
+    );
+}
+
+#[test]
+fn key_rotation_moves_open_disputes_to_the_new_key() {
+    let mut vault = vault("builders");
+    vault.account.open_disputes = 1;
+    let (dispute_key, dispute) = dispute(&vault);
+    let alice = dispute.initiator;
+    let rotated = Pubkey::new_unique();
+
+    let team_vault = Box::leak(Box::new(program_account(vault.key, &vault.account)));
+    let team_vault = Account::<TeamVault>::try_from(&*team_vault).unwrap();
+    let open_disputes = Box::leak(vec![program_account(dispute_key, &dispute)].into_boxed_slice());
+    move_founder_records(&team_vault, None, open_disputes, &alice, rotated).unwrap();
+
+    let moved = Account::<Dispute>::try_from(&open_disputes[0]).unwrap();
+    assert_eq!(moved.initiator, rotated);
+}
+
+#[test]
+fn key_rotation_carries_the_freeze_to_the_new_key() {
+    let mut vault = team_vault();
+    let alice = vault.founders[0];
+    vault.freeze(alice, PERIOD, 0).unwrap();
+
+    let rotated = Pubkey::new_unique();
+    vault.replace_founder(&alice, rotated).unwrap();
+
+    assert_eq!(vault.freezes[0].initiator, rotated);
+    assert_eq!(
+        vault.freeze(rotated, 2 * PERIOD, 10).unwrap_err(),
+        ErrorCode::FreezeAlreadyOpen.into()
+    );
+}
@@ -0,0 +1,269 @@
+//! Co-sponsored escrows take top-ups only where a basis-point milestone can
+//! still pay them out, keep dust contributors out, and refund to the
+//! contributors alone, holding back shares their accounts cannot take.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, Context, Pubkey};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token::state::AccountState;
+use common::{
+    assert_accepted, assert_rejected, escrow, milestone, none, program_account, signer,
+    token_account, token_account_at, token_program, try_accounts,
+};
+use rise_of_founders_protocol::rise_of_founders_protocol::{
+    cancel_escrow, close_escrow, fund_escrow, pay_owed_refund, reclaim_expired_milestone,
+};
+use rise_of_founders_protocol::{
+    CancelEscrow, CloseEscrow, Contribution, ErrorCode, EscrowStatus, FundEscrow, MilestoneShare,
+    PayOwedRefund, ReclaimToContributors, SponsorEscrow, ID,
+};
+
+/// Run fund_escrow for `contributor` against the escrow as given
+fn fund(
+    escrow_key: Pubkey,
+    escrow: &SponsorEscrow,
+    contributor: Pubkey,
+    amount: u64,
+) -> anchor_lang::Result<()> {
+    let mut accounts = try_accounts::<FundEscrow>(vec![
+        program_account(escrow_key, escrow),
+        token_account(escrow.mint, contributor),
+        token_account(escrow.mint, escrow_key),
+        signer(contributor),
+        token_program(),
+    ])
+    .unwrap();
+    fund_escrow(
+        Context::new(&ID, &mut accounts, &[], Default::default()),
+        amount,
+    )
+}
+
+#[test]
+fn fund_escrow_rejects_top_ups_only_fixed_amounts_would_sweep() {
+    let sponsor = Pubkey::new_unique();
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, Pubkey::new_unique());
+
+    escrow.milestones = vec![milestone(MilestoneShare::Amount(1_000))];
+    assert_rejected(
+        fund(escrow_key, &escrow, sponsor, 500),
+        ErrorCode::NoOpenMilestones,
+    );
+
+    escrow.milestones = vec![
+        milestone(MilestoneShare::Amount(500)),
+        milestone(MilestoneShare::Bps(10_000)),
+    ];
+    assert_accepted(fund(escrow_key, &escrow, sponsor, 500));
+}
+
+#[test]
+fn fund_escrow_rejects_dust_from_new_contributors() {
+    let sponsor = Pubkey::new_unique();
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, Pubkey::new_unique());
+    escrow.milestones = vec![milestone(MilestoneShare::Bps(10_000))];
+    escrow.min_contribution = 100;
+
+    assert_rejected(
+        fund(escrow_key, &escrow, Pubkey::new_unique(), 99),
+        ErrorCode::ContributionTooSmall,
+    );
+    assert_accepted(fund(escrow_key, &escrow, Pubkey::new_unique(), 100));
+    // Existing contributors may still top up by any amount
+    assert_accepted(fund(escrow_key, &escrow, sponsor, 1));
+}
+
+#[test]
+fn reclaim_expired_milestone_refunds_only_to_the_contributors() {
+    let sponsor = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, mint);
+    escrow.milestones = vec![milestone(MilestoneShare::Bps(10_000))];
+    let reclaim = |refund_to: Pubkey| {
+        let mut accounts = try_accounts::<ReclaimToContributors>(vec![
+            program_account(escrow_key, &escrow),
+            token_account(mint, escrow_key),
+            token_program(),
+        ])
+        .unwrap();
+        let contributor_accounts =
+            Box::leak(vec![token_account(mint, refund_to)].into_boxed_slice());
+        let ctx = Context::new(&ID, &mut accounts, contributor_accounts, Default::default());
+        reclaim_expired_milestone(ctx, 0)
+    };
+
+    assert_accepted(reclaim(sponsor));
+    assert_rejected(
+        reclaim(Pubkey::new_unique()),
+        ErrorCode::InvalidRecipientTokenAccount,
+    );
+}
+
+/// An escrow with 1 of 999 left to refund, split between a contributor of 1
+/// and the sponsor
+fn nearly_released_escrow(sponsor: Pubkey, small: Pubkey) -> (Pubkey, SponsorEscrow) {
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, Pubkey::new_unique());
+    escrow.contributions = vec![
+        Contribution {
+            contributor: small,
+            amount: 1,
+        },
+        Contribution {
+            contributor: sponsor,
+            amount: 999,
+        },
+    ];
+    escrow.released_amount = 999;
+    (escrow_key, escrow)
+}
+
+/// Run cancel_escrow with the given contributor accounts and return the
+/// escrow as the handler left it
+fn cancel(
+    escrow_key: Pubkey,
+    escrow: &SponsorEscrow,
+    contributor_accounts: Vec<AccountInfo<'static>>,
+) -> anchor_lang::Result<SponsorEscrow> {
+    let mut accounts = try_accounts::<CancelEscrow>(vec![
+        program_account(escrow_key, escrow),
+        token_account(escrow.mint, escrow_key),
+        signer(escrow.sponsor),
+        token_program(),
+    ])
+    .unwrap();
+    let contributor_accounts = Box::leak(contributor_accounts.into_boxed_slice());
+    cancel_escrow(Context::new(
+        &ID,
+        &mut accounts,
+        contributor_accounts,
+        Default::default(),
+    ))
+    .map(|()| accounts.escrow.clone().into_inner())
+}
+
+#[test]
+fn refunds_ignore_the_accounts_of_zero_shares() {
+    let (sponsor, small) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (escrow_key, escrow) = nearly_released_escrow(sponsor, small);
+
+    // The small contributor's share of 1 rounds down to nothing
+    let escrow = cancel(
+        escrow_key,
+        &escrow,
+        vec![none(), token_account(escrow.mint, sponsor)],
+    )
+    .unwrap();
+    assert!(escrow.status == EscrowStatus::Cancelled);
+    assert!(escrow.owed_refunds.is_empty());
+}
+
+#[test]
+fn refunds_to_unpayable_accounts_are_owed_instead() {
+    let (sponsor, co_sponsor) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, Pubkey::new_unique());
+    escrow.contributions.push(Contribution {
+        contributor: co_sponsor,
+        amount: 1_000,
+    });
+    escrow.total_amount = 2_000;
+    let frozen = token_account_at(
+        get_associated_token_address(&co_sponsor, &escrow.mint),
+        escrow.mint,
+        co_sponsor,
+        AccountState::Frozen,
+    );
+
+    let cancelled = cancel(
+        escrow_key,
+        &escrow,
+        vec![token_account(escrow.mint, sponsor), frozen],
+    )
+    .unwrap();
+    assert!(cancelled.status == EscrowStatus::Cancelled);
+    assert_eq!(cancelled.refunded_amount, 2_000);
+    assert_eq!(cancelled.owed_refunds.len(), 1);
+    assert_eq!(cancelled.owed_refunds[0].contributor, co_sponsor);
+    assert_eq!(cancelled.owed_refunds[0].amount, 1_000);
+
+    // Any other account still has to belong to the contributor
+    assert_rejected(
+        cancel(
+            escrow_key,
+            &escrow,
+            vec![
+                token_account(escrow.mint, sponsor),
+                token_account(escrow.mint, Pubkey::new_unique()),
+            ],
+        ),
+        ErrorCode::InvalidRecipientTokenAccount,
+    );
+}
+
+#[test]
+fn owed_refunds_are_paid_once_to_the_contributor() {
+    let (sponsor, co_sponsor) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, Pubkey::new_unique());
+    escrow.status = EscrowStatus::Cancelled;
+    escrow.owe_refund(co_sponsor, 400).unwrap();
+    escrow.owe_refund(co_sponsor, 100).unwrap();
+    assert_eq!(escrow.owed_refunds[0].amount, 500);
+
+    let pay = |escrow: &SponsorEscrow, owner: Pubkey| {
+        let mut accounts = try_accounts::<PayOwedRefund>(vec![
+            program_account(escrow_key, escrow),
+            token_account(escrow.mint, escrow_key),
+            token_account(escrow.mint, owner),
+            token_program(),
+        ])
+        .unwrap();
+        pay_owed_refund(Context::new(&ID, &mut accounts, &[], Default::default()))
+            .map(|()| accounts.escrow.clone().into_inner())
+    };
+
+    assert_rejected(pay(&escrow, sponsor), ErrorCode::NoRefundOwed);
+    let paid = pay(&escrow, co_sponsor).unwrap();
+    assert!(paid.owed_refunds.is_empty());
+    assert_rejected(pay(&paid, co_sponsor), ErrorCode::NoRefundOwed);
+}
+
+#[test]
+fn close_escrow_waits_for_owed_refunds() {
+    let sponsor = Pubkey::new_unique();
+    let (escrow_key, mut escrow) = escrow("quest-1", sponsor, Pubkey::new_unique());
+    escrow.status = EscrowStatus::Cancelled;
+    let close = |escrow: &SponsorEscrow, refund_to: AccountInfo<'static>| {
+        let mut accounts = try_accounts::<CloseEscrow>(vec![
+            program_account(escrow_key, escrow),
+            token_account(escrow.mint, escrow_key),
+            signer(sponsor),
+            token_program(),
+        ])
+        .unwrap();
+        let contributor_accounts = Box::leak(vec![refund_to].into_boxed_slice());
+        close_escrow(Context::new(
+            &ID,
+            &mut accounts,
+            contributor_accounts,
+            Default::default(),
+        ))
+    };
+    let frozen = || {
+        token_account_at(
+            get_associated_token_address(&sponsor, &escrow.mint),
+            escrow.mint,
+            sponsor,
+            AccountState::Frozen,
+        )
+    };
+
+    // Leftover dust owed to a frozen account cannot close with the escrow
+    assert_rejected(close(&escrow, frozen()), ErrorCode::RefundsOutstanding);
+    assert_accepted(close(&escrow, token_account(escrow.mint, sponsor)));
+
+    escrow.owe_refund(sponsor, 1).unwrap();
+    assert_rejected(
+        close(&escrow, token_account(escrow.mint, sponsor)),
+        ErrorCode::RefundsOutstanding,
+    );
+}
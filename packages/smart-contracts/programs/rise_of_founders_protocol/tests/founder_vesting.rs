@@ -0,0 +1,78 @@
+//! Founder vesting is linear between the cliff and the end of the schedule,
+//! and a revocation returns exactly what has not vested yet.
+
+use anchor_lang::prelude::Pubkey;
+use rise_of_founders_protocol::{ErrorCode, FounderVesting};
+
+/// Vests 1_000 tokens over 1_000 seconds from 100, with a 250 second cliff
+fn vesting(revocable: bool) -> FounderVesting {
+    let founder = Pubkey::new_unique();
+    FounderVesting {
+        team_vault: Pubkey::new_unique(),
+        founder,
+        beneficiary: founder,
+        mint: Pubkey::new_unique(),
+        total_amount: 1_000,
+        claimed: 0,
+        start_time: 100,
+        cliff_duration: 250,
+        vesting_duration: 1_000,
+        revocable,
+        revoked_at: None,
+        bump: 255,
+    }
+}
+
+#[test]
+fn nothing_vests_before_the_cliff() {
+    let vesting = vesting(true);
+
+    assert_eq!(vesting.vested_amount(i64::MIN).unwrap(), 0);
+    assert_eq!(vesting.vested_amount(349).unwrap(), 0);
+    assert_eq!(vesting.vested_amount(350).unwrap(), 250);
+}
+
+#[test]
+fn everything_vests_at_the_end() {
+    let vesting = vesting(true);
+
+    assert_eq!(vesting.vested_amount(1_099).unwrap(), 999);
+    assert_eq!(vesting.vested_amount(1_100).unwrap(), 1_000);
+    assert_eq!(vesting.vested_amount(i64::MAX).unwrap(), 1_000);
+}
+
+#[test]
+fn large_schedules_do_not_overflow() {
+    let vesting = FounderVesting {
+        total_amount: u64::MAX,
+        start_time: 0,
+        cliff_duration: 0,
+        vesting_duration: i64::MAX - 1,
+        ..vesting(true)
+    };
+
+    assert_eq!(vesting.vested_amount(i64::MAX / 2).unwrap(), u64::MAX / 2);
+    assert_eq!(vesting.vested_amount(i64::MAX).unwrap(), u64::MAX);
+}
+
+#[test]
+fn revoking_returns_the_unvested_remainder_once() {
+    let mut vesting = vesting(true);
+
+    assert_eq!(vesting.revoke(600).unwrap(), 500);
+    assert_eq!(vesting.vested_amount(i64::MAX).unwrap(), 500);
+    assert_eq!(
+        vesting.revoke(700).unwrap_err(),
+        ErrorCode::VestingNotRevocable.into()
+    );
+
+    // Revoked before the cliff, everything goes back
+    let mut vesting = self::vesting(true);
+    assert_eq!(vesting.revoke(349).unwrap(), 1_000);
+
+    let mut vesting = self::vesting(false);
+    assert_eq!(
+        vesting.revoke(600).unwrap_err(),
+        ErrorCode::VestingNotRevocable.into()
+    );
+}
@@ -0,0 +1,182 @@
+//! Proposals are decided by founder weight, quorum and threshold. Governance
+//! settings stay within bounds that keep proposal deadlines representable,
+//! and a stale founder agreement holds back every governance change except
+//! recovering or removing a founder.
+
+mod common;
+
+use anchor_lang::prelude::{Account, Pubkey};
+use common::{program_account, proposal, vault, Vault};
+use rise_of_founders_protocol::{
+    ErrorCode, GovernanceConfig, Proposal, ProposalStatus, ProposalType, Vote, MAX_EXECUTION_DELAY,
+    MAX_VOTING_PERIOD,
+};
+
+/// A vault of `founders`, weighted by `weights` when given
+fn weighted_vault(founders: usize, weights: Option<Vec<u16>>, threshold: u8) -> Vault {
+    let mut vault = vault("builders");
+    vault.account.founders = (0..founders).map(|_| Pubkey::new_unique()).collect();
+    vault.account.founder_weights = weights;
+    vault.account.threshold = threshold;
+    vault
+}
+
+/// An open proposal carrying one vote per `(founder index, support)`
+fn voted(vault: &Vault, votes: &[(usize, bool)]) -> Proposal {
+    let (_, mut proposal) = proposal(vault, Pubkey::new_unique());
+    proposal.status = ProposalStatus::Pending;
+    proposal.approved_at = None;
+    proposal.votes = votes
+        .iter()
+        .map(|(index, support)| Vote {
+            voter: vault.account.founders[*index],
+            delegator: None,
+            support: *support,
+            timestamp: 0,
+        })
+        .collect();
+    proposal
+}
+
+/// Tally at `now` and return the resulting status
+fn tally(vault: &Vault, proposal: &mut Proposal, now: i64) -> ProposalStatus {
+    let info = Box::leak(Box::new(program_account(vault.key, &vault.account)));
+    let team_vault = Account::try_from(&*info).unwrap();
+    proposal
+        .tally_votes(Pubkey::new_unique(), &team_vault, now)
+        .unwrap();
+    proposal.status.clone()
+}
+
+#[test]
+fn head_count_threshold_approves_at_exactly_the_threshold() {
+    let mut vault = weighted_vault(3, None, 2);
+    vault.account.governance.execution_delay = 3_600;
+
+    let mut proposal = voted(&vault, &[(0, true)]);
+    assert!(tally(&vault, &mut proposal, 100) == ProposalStatus::Pending);
+
+    let mut proposal = voted(&vault, &[(0, true), (1, true)]);
+    assert!(tally(&vault, &mut proposal, 100) == ProposalStatus::Approved);
+    assert_eq!(proposal.approved_at, Some(100));
+    assert_eq!(proposal.expires_at, 100 + 3_600 + 86_400);
+}
+
+#[test]
+fn weighted_threshold_rounds_up() {
+    // 67% of 10_000 needs 6_700, which two of the three founders miss
+    let vault = weighted_vault(3, Some(vec![3_333, 3_333, 3_334]), 67);
+
+    let mut proposal = voted(&vault, &[(0, true), (1, true)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Pending);
+
+    let mut proposal = voted(&vault, &[(0, true), (1, true), (2, true)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Approved);
+}
+
+#[test]
+fn majority_holder_needs_the_quorum_to_pass() {
+    let mut vault = weighted_vault(3, Some(vec![6_000, 2_000, 2_000]), 50);
+    vault.account.governance.quorum = Some(2);
+
+    let mut proposal = voted(&vault, &[(0, true)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Pending);
+
+    // A vote against still counts towards the quorum
+    let mut proposal = voted(&vault, &[(0, true), (1, false)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Approved);
+}
+
+#[test]
+fn rejected_once_the_threshold_is_out_of_reach() {
+    let vault = weighted_vault(3, Some(vec![6_000, 2_000, 2_000]), 50);
+
+    // 4_000 left cannot reach 5_000
+    let mut proposal = voted(&vault, &[(0, false)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Rejected);
+
+    // 6_000 left still can
+    let mut proposal = voted(&vault, &[(1, false), (2, false)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Pending);
+}
+
+#[test]
+fn recovery_is_decided_by_the_remaining_founders() {
+    let vault = weighted_vault(3, None, 3);
+    let recovery = |votes: &[(usize, bool)]| {
+        let mut proposal = voted(&vault, votes);
+        proposal.proposal_type = ProposalType::RecoverFounder {
+            lost_founder: vault.account.founders[2],
+            new_founder: Pubkey::new_unique(),
+        };
+        proposal
+    };
+
+    // The threshold of 3 scales down to 2 of the 2 remaining founders
+    let mut proposal = recovery(&[(0, true), (1, true)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Approved);
+
+    // The lost key voting in favour adds nothing, against vetoes
+    let mut proposal = recovery(&[(0, true), (2, true)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Pending);
+    let mut proposal = recovery(&[(0, true), (1, true), (2, false)]);
+    assert!(tally(&vault, &mut proposal, 0) == ProposalStatus::Rejected);
+}
+
+#[test]
+fn stale_agreements_block_governance_but_not_recovery_or_removal() {
+    let mut vault = weighted_vault(3, None, 2);
+    vault.account.agreement_required = true;
+    vault.account.agreement_current = false;
+    let lost_founder = vault.account.founders[2];
+
+    assert_eq!(
+        vault
+            .account
+            .validate_proposal_type(&ProposalType::ChangeThreshold { threshold: 3 })
+            .unwrap_err(),
+        ErrorCode::AgreementOutOfDate.into()
+    );
+    assert!(vault
+        .account
+        .validate_proposal_type(&ProposalType::RecoverFounder {
+            lost_founder,
+            new_founder: Pubkey::new_unique(),
+        })
+        .is_ok());
+    assert!(vault
+        .account
+        .validate_proposal_type(&ProposalType::RemoveFounder {
+            founder: lost_founder
+        })
+        .is_ok());
+}
+
+fn governance(voting_period: i64, execution_delay: i64) -> GovernanceConfig {
+    GovernanceConfig {
+        voting_period,
+        execution_delay,
+        quorum: None,
+    }
+}
+
+#[test]
+fn voting_period_and_timelock_are_capped() {
+    assert!(governance(MAX_VOTING_PERIOD, MAX_EXECUTION_DELAY)
+        .validate(2)
+        .is_ok());
+
+    for config in [
+        governance(0, 0),
+        governance(MAX_VOTING_PERIOD + 1, 0),
+        governance(i64::MAX, 0),
+        governance(86_400, -1),
+        governance(86_400, MAX_EXECUTION_DELAY + 1),
+        governance(86_400, i64::MAX),
+    ] {
+        assert_eq!(
+            config.validate(2).unwrap_err(),
+            ErrorCode::InvalidGovernanceConfig.into()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Milestone distribution proofs must verify every leaf of a tree built the
+//! way off-chain distributors build it, and nothing else. Claims against a
+//! distribution must also stay within its total and pay each leaf once.
+
+mod common;
+
+use anchor_lang::prelude::{Context, Pubkey};
+use anchor_lang::solana_program::keccak;
+use common::{escrow, program_account, signer, token_account, token_program, try_accounts};
+use rise_of_founders_protocol::rise_of_founders_protocol::claim_distribution;
+use rise_of_founders_protocol::{
+    distribution_leaf, verify_merkle_proof, ClaimDistribution, ErrorCode, MilestoneDistribution,
+    DISTRIBUTION_BITMAP_LEN, ID,
+};
+
+fn parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[&[1], &left, &right]).to_bytes()
+}
+
+/// Root and per-leaf proofs, carrying an odd node up a level unpaired
+fn tree(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut proofs = vec![Vec::new(); leaves.len()];
+    let mut level: Vec<(Vec<usize>, [u8; 32])> = leaves
+        .iter()
+        .enumerate()
+        .map(|(index, leaf)| (vec![index], *leaf))
+        .collect();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [(left_leaves, left), (right_leaves, right)] => {
+                    for index in left_leaves {
+                        proofs[*index].push(*right);
+                    }
+                    for index in right_leaves {
+                        proofs[*index].push(*left);
+                    }
+                    (
+                        [left_leaves.clone(), right_leaves.clone()].concat(),
+                        parent(*left, *right),
+                    )
+                }
+                [single] => single.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    (level[0].1, proofs)
+}
+
+#[test]
+fn every_leaf_verifies_against_the_root() {
+    let claims: Vec<(Pubkey, u64)> = (0..5)
+        .map(|i| (Pubkey::new_unique(), 100 * (i + 1)))
+        .collect();
+    let leaves: Vec<[u8; 32]> = claims
+        .iter()
+        .enumerate()
+        .map(|(index, (claimant, amount))| distribution_leaf(index as u32, claimant, *amount))
+        .collect();
+    let (root, proofs) = tree(&leaves);
+
+    for (leaf, proof) in leaves.iter().zip(proofs.iter()) {
+        assert!(verify_merkle_proof(proof, &root, *leaf));
+    }
+}
+
+#[test]
+fn altered_claims_do_not_verify() {
+    let claims: Vec<(Pubkey, u64)> = (0..4).map(|_| (Pubkey::new_unique(), 250)).collect();
+    let leaves: Vec<[u8; 32]> = claims
+        .iter()
+        .enumerate()
+        .map(|(index, (claimant, amount))| distribution_leaf(index as u32, claimant, *amount))
+        .collect();
+    let (root, proofs) = tree(&leaves);
+    let (claimant, amount) = claims[1];
+
+    assert!(verify_merkle_proof(
+        &proofs[1],
+        &root,
+        distribution_leaf(1, &claimant, amount)
+    ));
+    assert!(!verify_merkle_proof(
+        &proofs[1],
+        &root,
+        distribution_leaf(1, &claimant, amount + 1)
+    ));
+    assert!(!verify_merkle_proof(
+        &proofs[1],
+        &root,
+        distribution_leaf(2, &claimant, amount)
+    ));
+    assert!(!verify_merkle_proof(
+        &proofs[1],
+        &root,
+        distribution_leaf(1, &Pubkey::new_unique(), amount)
+    ));
+}
+
+// =============================================================================
+// CLAIMS
+// =============================================================================
+
+/// A milestone distribution reserving `total_amount` of the escrow, with a
+/// single-leaf tree paying `amount` to `claimant`
+fn claim_accounts(claimant: Pubkey, amount: u64, total_amount: u64) -> ClaimDistribution<'static> {
+    let mint = Pubkey::new_unique();
+    let (escrow_key, mut escrow) = escrow("quest-1", Pubkey::new_unique(), mint);
+    escrow.reserved_amount = total_amount;
+    let (distribution_key, distribution_bump) =
+        Pubkey::find_program_address(&[b"milestone_distribution", escrow_key.as_ref(), &[0]], &ID);
+    let distribution = MilestoneDistribution {
+        escrow: escrow_key,
+        milestone_index: 0,
+        merkle_root: distribution_leaf(0, &claimant, amount),
+        total_amount,
+        claimed_amount: 0,
+        claimant_count: 1,
+        expires_at: i64::MAX,
+        claimed_bitmap: vec![0; DISTRIBUTION_BITMAP_LEN],
+        bump: distribution_bump,
+    };
+
+    try_accounts(vec![
+        program_account(escrow_key, &escrow),
+        program_account(distribution_key, &distribution),
+        token_account(mint, escrow_key),
+        token_account(mint, claimant),
+        signer(claimant),
+        token_program(),
+    ])
+    .unwrap()
+}
+
+fn claim(accounts: &mut ClaimDistribution<'static>, amount: u64) -> anchor_lang::Result<()> {
+    let ctx = Context::new(&ID, accounts, &[], Default::default());
+    claim_distribution(ctx, 0, amount, Vec::new())
+}
+
+#[test]
+fn claims_cannot_exceed_the_distribution_total() {
+    let claimant = Pubkey::new_unique();
+    let mut accounts = claim_accounts(claimant, 150, 100);
+
+    assert_eq!(
+        claim(&mut accounts, 150).unwrap_err(),
+        ErrorCode::DistributionExceeded.into()
+    );
+    assert_eq!(accounts.distribution.claimed_amount, 0);
+    assert!(!accounts.distribution.is_claimed(0));
+}
+
+#[test]
+fn each_leaf_pays_out_once() {
+    let claimant = Pubkey::new_unique();
+    let mut accounts = claim_accounts(claimant, 100, 100);
+
+    claim(&mut accounts, 100).unwrap();
+    assert!(accounts.distribution.is_claimed(0));
+    assert_eq!(accounts.distribution.claimed_amount, 100);
+    assert_eq!(accounts.escrow.reserved_amount, 0);
+
+    assert_eq!(
+        claim(&mut accounts, 100).unwrap_err(),
+        ErrorCode::DistributionAlreadyClaimed.into()
+    );
+    assert_eq!(accounts.distribution.claimed_amount, 100);
+}
@@ -0,0 +1,110 @@
+//! Milestone payouts must add up to exactly the milestone pool, whatever
+//! order the milestones settle in and however the shares round.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::milestone;
+use rise_of_founders_protocol::{Contribution, Milestone, MilestoneShare, SponsorEscrow};
+
+fn escrow(total_amount: u64, prize_pool: u64, milestones: Vec<Milestone>) -> SponsorEscrow {
+    let (_, mut escrow) = common::escrow("quest-1", Pubkey::new_unique(), Pubkey::new_unique());
+    escrow.total_amount = total_amount;
+    escrow.prize_table.pool = prize_pool;
+    escrow.milestones = milestones;
+    escrow
+}
+
+/// Settle milestones in `order` the way release_milestone does, returning
+/// each payout
+fn settle(escrow: &mut SponsorEscrow, order: &[usize]) -> Vec<u64> {
+    order
+        .iter()
+        .map(|index| {
+            let amount = escrow.milestone_amount(*index).unwrap();
+            escrow.milestones[*index].released = true;
+            escrow.milestones[*index].settled_amount = amount;
+            escrow.released_amount = escrow.released_amount.checked_add(amount).unwrap();
+            amount
+        })
+        .collect()
+}
+
+#[test]
+fn final_milestone_sweeps_rounding_dust() {
+    let shares = [3_333, 3_333, 3_334].map(MilestoneShare::Bps).to_vec();
+    let mut escrow = escrow(1_000_001, 0, shares.into_iter().map(milestone).collect());
+
+    assert_eq!(
+        settle(&mut escrow, &[2, 0, 1]),
+        vec![333_400, 333_300, 333_301]
+    );
+    assert_eq!(escrow.released_amount, escrow.total_amount);
+    assert!(escrow.is_settled());
+}
+
+#[test]
+fn fixed_amounts_come_off_before_basis_points() {
+    let milestones = vec![
+        milestone(MilestoneShare::Amount(100)),
+        milestone(MilestoneShare::Bps(5_000)),
+        milestone(MilestoneShare::Bps(5_000)),
+    ];
+    // 1_000 of the 1_201 goes to prizes, leaving 201 for milestones
+    let mut escrow = escrow(1_201, 1_000, milestones);
+
+    assert_eq!(settle(&mut escrow, &[1, 0, 2]), vec![50, 100, 51]);
+    assert_eq!(escrow.released_amount, escrow.milestone_pool().unwrap());
+}
+
+#[test]
+fn top_ups_grow_the_milestones_still_to_be_paid() {
+    let shares = [2_500, 2_500, 5_000].map(MilestoneShare::Bps).to_vec();
+    let mut escrow = escrow(1_000, 0, shares.into_iter().map(milestone).collect());
+
+    assert_eq!(settle(&mut escrow, &[0]), vec![250]);
+
+    // A co-sponsor tops the escrow up to 2_000
+    escrow.total_amount += 1_000;
+
+    assert_eq!(settle(&mut escrow, &[1, 2]), vec![500, 1_250]);
+    assert_eq!(escrow.released_amount, escrow.total_amount);
+}
+
+#[test]
+fn judge_approval_needs_the_threshold_from_actual_judges() {
+    let shares = [5_000, 5_000].map(MilestoneShare::Bps).to_vec();
+    let mut escrow = escrow(1_000, 0, shares.into_iter().map(milestone).collect());
+
+    // Without judges the sponsor alone decides, so nothing is locked in
+    assert!(!escrow.is_judge_approved(0));
+
+    let judges = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    escrow.judges = judges.clone();
+    escrow.judge_threshold = 2;
+    escrow.milestones[0].approvals = vec![judges[0]];
+    assert!(!escrow.is_judge_approved(0));
+
+    escrow.milestones[0].approvals.push(judges[1]);
+    assert!(escrow.is_judge_approved(0));
+    assert!(!escrow.is_judge_approved(1));
+}
+
+#[test]
+fn refunds_split_pro_rata_with_dust_to_the_last_contributor() {
+    let mut escrow = escrow(1_000, 0, Vec::new());
+    escrow.contributions = [700, 200, 100]
+        .map(|amount| Contribution {
+            contributor: Pubkey::new_unique(),
+            amount,
+        })
+        .to_vec();
+
+    let refunds: Vec<u64> = escrow
+        .refund_shares(101)
+        .unwrap()
+        .iter()
+        .map(|refund| refund.amount)
+        .collect();
+    assert_eq!(refunds, vec![70, 20, 11]);
+}
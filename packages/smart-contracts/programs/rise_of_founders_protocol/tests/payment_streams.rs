@@ -0,0 +1,92 @@
+//! Payment streams vest by the second between their cliff and end, and
+//! proposals for streams whose total cannot be represented are refused.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{payment_stream, team_vault, vault};
+use rise_of_founders_protocol::{ErrorCode, PaymentStream, ProposalType};
+
+/// Streams 1 token a second from 100 to 1_100, with a cliff at 400
+fn stream() -> PaymentStream {
+    let (_, stream) = payment_stream(
+        &vault("builders"),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    PaymentStream {
+        start_time: 100,
+        cliff_time: 400,
+        end_time: 1_100,
+        ..stream
+    }
+}
+
+fn create_stream(rate_per_second: u64, start_time: i64, end_time: i64) -> ProposalType {
+    ProposalType::CreatePaymentStream {
+        recipient: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        rate_per_second,
+        start_time,
+        cliff_time: start_time,
+        end_time,
+    }
+}
+
+#[test]
+fn nothing_vests_before_the_cliff() {
+    let stream = stream();
+
+    assert_eq!(stream.vested_amount(i64::MIN).unwrap(), 0);
+    assert_eq!(stream.vested_amount(399).unwrap(), 0);
+    // At the cliff everything since the start vests at once
+    assert_eq!(stream.vested_amount(400).unwrap(), 300);
+}
+
+#[test]
+fn vesting_stops_at_the_end() {
+    let stream = stream();
+
+    assert_eq!(stream.vested_amount(1_099).unwrap(), 999);
+    assert_eq!(stream.vested_amount(1_100).unwrap(), 1_000);
+    assert_eq!(stream.vested_amount(i64::MAX).unwrap(), 1_000);
+}
+
+#[test]
+fn cancelling_freezes_what_has_vested() {
+    let mut stream = stream();
+
+    stream.cancelled_at = Some(700);
+    assert_eq!(stream.vested_amount(i64::MAX).unwrap(), 600);
+
+    // Cancelled before the cliff, nothing ever vests
+    stream.cancelled_at = Some(399);
+    assert_eq!(stream.vested_amount(i64::MAX).unwrap(), 0);
+}
+
+#[test]
+fn stream_totals_must_fit_a_token_amount() {
+    let vault = team_vault("builders");
+
+    assert!(vault
+        .validate_proposal_type(&create_stream(u64::MAX, 0, 1))
+        .is_ok());
+    assert_eq!(
+        vault
+            .validate_proposal_type(&create_stream(u64::MAX, 0, 2))
+            .unwrap_err(),
+        ErrorCode::InvalidPaymentStream.into()
+    );
+    assert_eq!(
+        vault
+            .validate_proposal_type(&create_stream(1, 0, 0))
+            .unwrap_err(),
+        ErrorCode::InvalidPaymentStream.into()
+    );
+    assert_eq!(
+        vault
+            .validate_proposal_type(&create_stream(1, i64::MIN, i64::MAX))
+            .unwrap_err(),
+        ErrorCode::MathOverflow.into()
+    );
+}
@@ -0,0 +1,189 @@
+//! Judges each back their own ranked list of winners, prize tables keep their
+//! deadlines representable, and judges who miss the results deadline lose
+//! their hold on the prize pool.
+
+mod common;
+
+use anchor_lang::prelude::{Context, Pubkey};
+use common::{
+    assert_accepted, assert_rejected, milestone, program_account, signer, token_account,
+    token_program, try_accounts,
+};
+use rise_of_founders_protocol::rise_of_founders_protocol::{
+    cancel_escrow, finalize_results, reclaim_unclaimed_prizes,
+};
+use rise_of_founders_protocol::{
+    CancelEscrow, ErrorCode, JudgeEscrow, MilestoneShare, PrizeTable, ReclaimToContributors,
+    SponsorEscrow, ID, MAX_CLAIM_WINDOW, MAX_RESULTS_PERIOD,
+};
+
+fn prize_table(claim_window: i64) -> PrizeTable {
+    PrizeTable {
+        pool: 1_000,
+        rank_bps: vec![6_000, 4_000],
+        claim_window,
+        results_deadline: 1,
+    }
+}
+
+fn escrow(judges: Vec<Pubkey>, judge_threshold: u8) -> SponsorEscrow {
+    let (_, escrow) = common::escrow("quest-1", Pubkey::new_unique(), Pubkey::new_unique());
+    SponsorEscrow {
+        judges,
+        judge_threshold,
+        prize_table: prize_table(86_400),
+        prizes_settled: false,
+        ..escrow
+    }
+}
+
+/// An escrow with one judge whose results are due at `results_deadline`
+fn judged_escrow(judge: Pubkey, results_deadline: i64) -> (Pubkey, SponsorEscrow) {
+    let (key, _) = common::escrow("quest-1", Pubkey::new_unique(), Pubkey::new_unique());
+    let mut escrow = escrow(vec![judge], 1);
+    escrow.prize_table.results_deadline = results_deadline;
+    (key, escrow)
+}
+
+#[test]
+fn a_dissenting_judge_does_not_reset_the_others() {
+    let judges: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut escrow = escrow(judges.clone(), 2);
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_eq!(escrow.submit_results(judges[0], &[alice, bob]).unwrap(), 1);
+    assert_eq!(escrow.submit_results(judges[1], &[bob, alice]).unwrap(), 1);
+    assert_eq!(escrow.submit_results(judges[2], &[alice, bob]).unwrap(), 2);
+}
+
+#[test]
+fn judges_can_change_only_their_own_list() {
+    let judges: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+    let mut escrow = escrow(judges.clone(), 2);
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    escrow.submit_results(judges[0], &[alice, bob]).unwrap();
+    assert_eq!(
+        escrow.submit_results(judges[0], &[alice, bob]).unwrap_err(),
+        ErrorCode::ResultsAlreadyApproved.into()
+    );
+
+    assert_eq!(escrow.submit_results(judges[1], &[bob]).unwrap(), 1);
+    assert_eq!(escrow.submit_results(judges[0], &[bob]).unwrap(), 2);
+    assert_eq!(escrow.result_votes.len(), 2);
+}
+
+#[test]
+fn claim_windows_are_capped() {
+    assert!(prize_table(MAX_CLAIM_WINDOW).validate(1_000, 0).is_ok());
+    assert_eq!(
+        prize_table(MAX_CLAIM_WINDOW + 1)
+            .validate(1_000, 0)
+            .unwrap_err(),
+        ErrorCode::InvalidPrizeTable.into()
+    );
+    assert_eq!(
+        prize_table(i64::MAX).validate(1_000, 0).unwrap_err(),
+        ErrorCode::InvalidPrizeTable.into()
+    );
+}
+
+#[test]
+fn results_deadlines_are_in_the_future_and_capped() {
+    let with_deadline = |results_deadline| PrizeTable {
+        results_deadline,
+        ..prize_table(86_400)
+    };
+
+    assert!(with_deadline(MAX_RESULTS_PERIOD).validate(1_000, 0).is_ok());
+    assert!(with_deadline(i64::MAX)
+        .validate(1_000, i64::MAX - 1)
+        .is_ok());
+    for (results_deadline, now) in [(10, 10), (MAX_RESULTS_PERIOD + 11, 10)] {
+        assert_eq!(
+            with_deadline(results_deadline)
+                .validate(1_000, now)
+                .unwrap_err(),
+            ErrorCode::InvalidPrizeTable.into()
+        );
+    }
+}
+
+#[test]
+fn finalize_results_closes_at_the_results_deadline() {
+    let judge = Pubkey::new_unique();
+    let finalize = |results_deadline| {
+        let (key, escrow) = judged_escrow(judge, results_deadline);
+        let mut accounts =
+            try_accounts::<JudgeEscrow>(vec![program_account(key, &escrow), signer(judge)])
+                .unwrap();
+        finalize_results(
+            Context::new(&ID, &mut accounts, &[], Default::default()),
+            vec![Pubkey::new_unique()],
+        )
+    };
+
+    assert_rejected(finalize(0), ErrorCode::ResultsDeadlinePassed);
+    assert_accepted(finalize(1));
+}
+
+#[test]
+fn reclaim_unclaimed_prizes_returns_the_pool_once_results_are_overdue() {
+    let judge = Pubkey::new_unique();
+    let reclaim = |results_deadline| {
+        let (key, escrow) = judged_escrow(judge, results_deadline);
+        let mut accounts = try_accounts::<ReclaimToContributors>(vec![
+            program_account(key, &escrow),
+            token_account(escrow.mint, key),
+            token_program(),
+        ])
+        .unwrap();
+        let contributor_accounts =
+            Box::leak(vec![token_account(escrow.mint, escrow.sponsor)].into_boxed_slice());
+        reclaim_unclaimed_prizes(Context::new(
+            &ID,
+            &mut accounts,
+            contributor_accounts,
+            Default::default(),
+        ))
+        .map(|()| accounts.escrow)
+    };
+
+    assert_rejected(reclaim(1), ErrorCode::ResultsNotFinalized);
+    let escrow = reclaim(0).unwrap();
+    assert_eq!(escrow.refunded_amount, 1_000);
+    assert!(escrow.prizes_settled);
+}
+
+#[test]
+fn cancel_escrow_skips_judges_once_results_are_overdue() {
+    let judge = Pubkey::new_unique();
+    let cancel = |results_deadline, approved_milestone: bool| {
+        let (key, mut escrow) = judged_escrow(judge, results_deadline);
+        let mut approved = milestone(MilestoneShare::Bps(10_000));
+        if approved_milestone {
+            approved.approvals = vec![judge];
+        }
+        escrow.milestones = vec![approved];
+        let mut accounts = try_accounts::<CancelEscrow>(vec![
+            program_account(key, &escrow),
+            token_account(escrow.mint, key),
+            signer(escrow.sponsor),
+            token_program(),
+        ])
+        .unwrap();
+        let contributor_accounts =
+            Box::leak(vec![token_account(escrow.mint, escrow.sponsor)].into_boxed_slice());
+        cancel_escrow(Context::new(
+            &ID,
+            &mut accounts,
+            contributor_accounts,
+            Default::default(),
+        ))
+    };
+
+    assert_rejected(cancel(1, false), ErrorCode::CancellationNotApproved);
+    assert_accepted(cancel(0, false));
+    // Milestones the judges approved are still owed to the recipients
+    assert_rejected(cancel(0, true), ErrorCode::MilestoneAwaitingRelease);
+}
@@ -0,0 +1,185 @@
+//! Off-chain votes are read from ed25519 precompile instructions. Only
+//! self-contained signatures are read, and only messages about the proposal
+//! being voted on count, so other signatures can share the transaction.
+
+use anchor_lang::prelude::Pubkey;
+use rise_of_founders_protocol::{
+    ed25519_signed_messages, signed_vote_message, signed_vote_prefix, signed_vote_support,
+    ErrorCode,
+};
+
+const HEADER_LEN: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+struct Entry {
+    public_key: Pubkey,
+    message: Vec<u8>,
+    instruction_index: u16,
+}
+
+fn entry(message: &[u8]) -> Entry {
+    Entry {
+        public_key: Pubkey::new_unique(),
+        message: message.to_vec(),
+        instruction_index: CURRENT_INSTRUCTION,
+    }
+}
+
+/// Lay entries out the way the precompile expects: header, offsets table,
+/// then each public key, signature and message
+fn ed25519_instruction(entries: &[Entry]) -> Vec<u8> {
+    let mut data = vec![entries.len() as u8, 0];
+    let mut payload = Vec::new();
+    let payload_start = HEADER_LEN + entries.len() * OFFSETS_LEN;
+
+    for entry in entries {
+        let public_key_offset = payload_start + payload.len();
+        payload.extend_from_slice(entry.public_key.as_ref());
+        let signature_offset = payload_start + payload.len();
+        payload.extend_from_slice(&[0; 64]);
+        let message_offset = payload_start + payload.len();
+        payload.extend_from_slice(&entry.message);
+
+        for value in [
+            signature_offset as u16,
+            entry.instruction_index,
+            public_key_offset as u16,
+            entry.instruction_index,
+            message_offset as u16,
+            entry.message.len() as u16,
+            entry.instruction_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    data.extend_from_slice(&payload);
+    data
+}
+
+/// Overwrite one u16 of the first entry's offsets
+fn set_offset(data: &mut [u8], at: usize, value: u16) {
+    data[HEADER_LEN + at..HEADER_LEN + at + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+#[test]
+fn reads_every_self_contained_signature() {
+    let first = entry(b"first");
+    let second = entry(b"second");
+    let expected = vec![
+        (first.public_key, first.message.clone()),
+        (second.public_key, second.message.clone()),
+    ];
+
+    assert_eq!(
+        ed25519_signed_messages(&ed25519_instruction(&[first, second])).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn skips_signatures_over_other_instructions() {
+    let own = entry(b"own");
+    let expected = vec![(own.public_key, own.message.clone())];
+    let data = ed25519_instruction(&[entry(b"foreign"), own]);
+
+    // Signature, public key and message instruction indexes
+    for foreign_field in [2, 6, 12] {
+        let mut data = data.clone();
+        set_offset(&mut data, foreign_field, 0);
+
+        assert_eq!(ed25519_signed_messages(&data).unwrap(), expected);
+    }
+}
+
+#[test]
+fn rejects_malformed_instructions() {
+    let well_formed = ed25519_instruction(&[entry(b"vote")]);
+    let data_len = well_formed.len() as u16;
+
+    let mut truncated_offsets = well_formed.clone();
+    truncated_offsets.truncate(HEADER_LEN + OFFSETS_LEN - 1);
+    let mut public_key_out_of_bounds = well_formed.clone();
+    set_offset(&mut public_key_out_of_bounds, 4, data_len - 31);
+    let mut message_out_of_bounds = well_formed.clone();
+    set_offset(&mut message_out_of_bounds, 10, 5);
+    set_offset(&mut message_out_of_bounds, 8, data_len - 4);
+    let mut message_offset_overflow = well_formed;
+    set_offset(&mut message_offset_overflow, 8, u16::MAX);
+
+    for data in [
+        vec![],
+        truncated_offsets,
+        public_key_out_of_bounds,
+        message_out_of_bounds,
+        message_offset_overflow,
+    ] {
+        assert_eq!(
+            ed25519_signed_messages(&data).unwrap_err(),
+            ErrorCode::InvalidSignedVote.into()
+        );
+    }
+}
+
+#[test]
+fn counts_only_votes_on_this_proposal() {
+    let vault = Pubkey::new_unique();
+    let proposal = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+
+    assert_eq!(
+        signed_vote_support(
+            &signed_vote_message(&vault, &proposal, true, 3),
+            &vault,
+            &proposal,
+            3
+        )
+        .unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        signed_vote_support(
+            &signed_vote_message(&vault, &proposal, false, 3),
+            &vault,
+            &proposal,
+            3
+        )
+        .unwrap(),
+        Some(false)
+    );
+
+    for foreign in [
+        signed_vote_message(&vault, &other, true, 3),
+        signed_vote_message(&other, &proposal, true, 3),
+        b"an unrelated signed message".to_vec(),
+    ] {
+        assert_eq!(
+            signed_vote_support(&foreign, &vault, &proposal, 3).unwrap(),
+            None
+        );
+    }
+}
+
+#[test]
+fn rejects_malformed_votes_on_this_proposal() {
+    let vault = Pubkey::new_unique();
+    let proposal = Pubkey::new_unique();
+    let mut trailing = signed_vote_message(&vault, &proposal, true, 3);
+    trailing.push(0);
+    let mut bad_support = signed_vote_prefix(&vault, &proposal);
+    bad_support.push(2);
+    bad_support.extend_from_slice(&3u64.to_le_bytes());
+
+    for message in [
+        signed_vote_message(&vault, &proposal, true, 2),
+        signed_vote_prefix(&vault, &proposal),
+        trailing,
+        bad_support,
+    ] {
+        assert_eq!(
+            signed_vote_support(&message, &vault, &proposal, 3).unwrap_err(),
+            ErrorCode::InvalidSignedVote.into()
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! A separated vault hands out tokens that reach it after settling, and
+//! closing it shrinks the account to a tombstone whose spare rent goes back
+//! to the founders.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, Context, Pubkey};
+use anchor_lang::solana_program::rent::Rent;
+use anchor_lang::{AnchorSerialize, ToAccountInfo};
+use common::{
+    account_info, assert_rejected, program_account, proposal, resizable_program_account, signer,
+    token_account, token_program, try_accounts, vault, Vault,
+};
+use rise_of_founders_protocol::rise_of_founders_protocol::{
+    close_team_vault, sweep_separated_vault,
+};
+use rise_of_founders_protocol::{
+    CloseTeamVault, ErrorCode, ProposalStatus, ProposalType, SeparationShare, SweepSeparatedVault,
+    ID,
+};
+
+/// A vault whose separation has settled, with every tracked mint drained
+fn separated_vault() -> Vault {
+    let mut vault = vault("builders");
+    vault.account.is_active = false;
+    vault.account.balances = Vec::new();
+    vault
+}
+
+fn founder_accounts(vault: &Vault) -> &'static [AccountInfo<'static>] {
+    Box::leak(
+        vault
+            .account
+            .founders
+            .iter()
+            .map(|founder| account_info(*founder, Pubkey::default(), Vec::new(), false, false))
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+    )
+}
+
+fn sweep(vault: &Vault, status: ProposalStatus) -> anchor_lang::Result<()> {
+    let (proposal_key, mut proposal) = proposal(vault, Pubkey::new_unique());
+    proposal.mint = None;
+    proposal.status = status;
+    proposal.proposal_type = ProposalType::SeparateTeam {
+        distribution: vault
+            .account
+            .founders
+            .iter()
+            .map(|founder| SeparationShare {
+                founder: *founder,
+                share_bps: 5_000,
+            })
+            .collect(),
+    };
+    // A mint the vault never tracked
+    let mint = Pubkey::new_unique();
+
+    let mut accounts = try_accounts::<SweepSeparatedVault>(vec![
+        program_account(vault.key, &vault.account),
+        program_account(proposal_key, &proposal),
+        token_account(mint, vault.key),
+        signer(Pubkey::new_unique()),
+        token_program(),
+    ])
+    .unwrap();
+    let founder_token_accounts = Box::leak(
+        vault
+            .account
+            .founders
+            .iter()
+            .map(|founder| token_account(mint, *founder))
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+    );
+    sweep_separated_vault(Context::new(
+        &ID,
+        &mut accounts,
+        founder_token_accounts,
+        Default::default(),
+    ))
+}
+
+#[test]
+fn untracked_tokens_are_swept_only_after_the_separation_settles() {
+    sweep(&separated_vault(), ProposalStatus::Executed).unwrap();
+
+    assert_rejected(
+        sweep(&separated_vault(), ProposalStatus::PartiallyExecuted),
+        ErrorCode::TeamVaultNotSettled,
+    );
+
+    let mut closed = separated_vault();
+    closed.account.closed = true;
+    assert_rejected(
+        sweep(&closed, ProposalStatus::Executed),
+        ErrorCode::TeamVaultAlreadyClosed,
+    );
+}
+
+#[test]
+fn closing_shrinks_the_vault_and_refunds_the_founders() {
+    let vault = separated_vault();
+    let founders = founder_accounts(&vault);
+    let mut accounts = try_accounts::<CloseTeamVault>(vec![
+        resizable_program_account(vault.key, &vault.account),
+        signer(vault.account.founders[0]),
+    ])
+    .unwrap();
+
+    close_team_vault(Context::new(
+        &ID,
+        &mut accounts,
+        founders,
+        Default::default(),
+    ))
+    .unwrap();
+
+    let team_vault = &accounts.team_vault;
+    assert!(team_vault.closed);
+    assert_eq!(team_vault.team_id, "builders");
+    assert!(team_vault.founders.is_empty());
+
+    let space = 8 + team_vault.try_to_vec().unwrap().len();
+    let rent = Rent::default().minimum_balance(space);
+    let vault_info = team_vault.to_account_info();
+    assert_eq!(vault_info.data_len(), space);
+    assert_eq!(vault_info.lamports(), rent);
+
+    let refunded = 1_000_000_000 - rent;
+    assert_eq!(founders[0].lamports(), 1_000_000_000 + refunded / 2);
+    assert_eq!(
+        founders[1].lamports(),
+        1_000_000_000 + refunded - refunded / 2
+    );
+}
+
+#[test]
+fn closing_needs_every_founder_in_order() {
+    let vault = separated_vault();
+    let mut founders = founder_accounts(&vault).to_vec();
+    founders.reverse();
+    let mut accounts = try_accounts::<CloseTeamVault>(vec![
+        resizable_program_account(vault.key, &vault.account),
+        signer(vault.account.founders[0]),
+    ])
+    .unwrap();
+
+    assert_rejected(
+        close_team_vault(Context::new(
+            &ID,
+            &mut accounts,
+            Box::leak(founders.into_boxed_slice()),
+            Default::default(),
+        )),
+        ErrorCode::MissingFounderAccounts,
+    );
+}
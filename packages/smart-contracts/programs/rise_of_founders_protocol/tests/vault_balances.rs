@@ -0,0 +1,44 @@
+//! Vault funds are tracked per mint, so moving one mint can never be paid
+//! for out of another mint's balance.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::team_vault;
+use rise_of_founders_protocol::{ErrorCode, MAX_VAULT_MINTS};
+
+#[test]
+fn debits_only_draw_on_their_own_mint() {
+    let mut vault = team_vault("team-1");
+    let (usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_eq!(vault.credit(usdc, 100).unwrap(), 100);
+    assert_eq!(vault.credit(bonk, 5).unwrap(), 5);
+
+    assert_eq!(
+        vault.debit(bonk, 50).unwrap_err(),
+        ErrorCode::InsufficientVaultFunds.into()
+    );
+    assert_eq!(vault.debit(usdc, 40).unwrap(), 60);
+    assert_eq!(vault.balance_of(&usdc), 60);
+    assert_eq!(vault.balance_of(&bonk), 5);
+}
+
+#[test]
+fn emptied_mints_stop_taking_a_slot() {
+    let mut vault = team_vault("team-1");
+    let mints: Vec<Pubkey> = (0..MAX_VAULT_MINTS).map(|_| Pubkey::new_unique()).collect();
+    for mint in &mints {
+        vault.credit(*mint, 1).unwrap();
+    }
+
+    let extra = Pubkey::new_unique();
+    assert_eq!(
+        vault.credit(extra, 1).unwrap_err(),
+        ErrorCode::TooManyVaultMints.into()
+    );
+
+    assert_eq!(vault.debit(mints[0], 1).unwrap(), 0);
+    assert_eq!(vault.balances.len(), MAX_VAULT_MINTS - 1);
+    assert_eq!(vault.credit(extra, 1).unwrap(), 1);
+}
@@ -0,0 +1,25 @@
+//! Instruction proposals reconcile the vault's tracked balances against the
+//! vault token accounts they touch.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{token_account, token_program};
+use rise_of_founders_protocol::vault_token_holdings;
+
+#[test]
+fn vault_instructions_reconcile_every_vault_token_account_once() {
+    let (vault_key, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let vault_account = token_account(mint, vault_key);
+    let accounts = vec![
+        vault_account.clone(),
+        vault_account,
+        token_account(mint, Pubkey::new_unique()),
+        token_program(),
+    ];
+
+    assert_eq!(
+        vault_token_holdings(&accounts, &vault_key),
+        vec![(mint, 1_000_000)]
+    );
+}